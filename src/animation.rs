@@ -0,0 +1,393 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use crate::damage::DamageEvent;
+use crate::{Attack, Health, Movement};
+
+const ANIMATIONS_RON: &str = include_str!("../assets/data/animations.ron");
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnimationClip {
+    Idle,
+    Walk,
+    Attack,
+    Hurt,
+    Die,
+}
+
+impl AnimationClip {
+    // A clip only interrupts clips with a lower priority
+    fn priority(&self) -> u8 {
+        match self {
+            AnimationClip::Idle | AnimationClip::Walk => 0,
+            AnimationClip::Attack => 1,
+            AnimationClip::Hurt => 2,
+            AnimationClip::Die => 3,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ClipDef {
+    pub frames: Vec<usize>,
+    pub fps: f32,
+    #[serde(default)]
+    pub looping: bool, // clips that do not loop hold their last frame until the next clip starts
+    #[serde(default)]
+    pub events: HashMap<usize, String>, // index into `frames` -> event name
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SheetDef {
+    pub frame_size: (u32, u32),
+    pub columns: u32,
+    pub rows: u32,
+    pub clips: HashMap<AnimationClip, ClipDef>,
+}
+
+/// Every sprite sheet from `assets/data/animations.ron`, keyed by image path
+#[derive(Resource)]
+pub struct SpriteSheets {
+    sheets: HashMap<String, SheetDef>,
+    layouts: HashMap<String, Handle<TextureAtlasLayout>>, // filled in by setup_sprite_sheets
+}
+
+impl Default for SpriteSheets {
+    fn default() -> Self {
+        let sheets = ron::from_str(ANIMATIONS_RON).unwrap_or_else(|error| {
+            error!("Could not read animations.ron: {}", error);
+            HashMap::new()
+        });
+        Self { sheets, layouts: HashMap::new() }
+    }
+}
+
+impl SpriteSheets {
+    // Whether a clip of the sheet has a frame with the named event
+    pub fn has_event(&self, sheet: &str, clip: AnimationClip, name: &str) -> bool {
+        self.sheets
+            .get(sheet)
+            .and_then(|sheet| sheet.clips.get(&clip))
+            .is_some_and(|def| def.events.values().any(|event| event == name))
+    }
+}
+
+/// Fired when an animation reaches a frame with an event, e.g. "hit" on the impact frame of an attack
+#[derive(EntityEvent)]
+pub struct AnimationEvent {
+    pub entity: Entity,
+    pub clip: AnimationClip,
+    pub name: String,
+}
+
+/// Plays the clips of a sprite sheet on the unit's Sprite, picked from what the unit is doing
+#[derive(Component)]
+pub struct Animator {
+    sheet: String,
+    clip: AnimationClip,
+    frame: usize, // index into the clip's frames
+    timer: Timer,
+    started: bool, // the first frame of the clip was shown and its events fired
+    finished: bool,
+    hurt: bool,         // set by damage, played on the next update
+    last_cooldown: f32, // an attack cooldown going up means the unit just attacked
+}
+
+impl Animator {
+    pub fn new(sheet: impl Into<String>) -> Self {
+        Self {
+            sheet: sheet.into(),
+            clip: AnimationClip::Idle,
+            frame: 0,
+            timer: Timer::from_seconds(1.0, TimerMode::Repeating),
+            started: false,
+            finished: false,
+            hurt: false,
+            last_cooldown: 0.0,
+        }
+    }
+
+    pub fn clip(&self) -> AnimationClip {
+        self.clip
+    }
+
+    pub fn sheet(&self) -> &str {
+        &self.sheet
+    }
+
+    fn play(&mut self, clip: AnimationClip, def: &ClipDef) {
+        self.clip = clip;
+        self.frame = 0;
+        self.started = false;
+        self.finished = false;
+        self.timer = Timer::from_seconds(1.0 / def.fps.max(0.01), TimerMode::Repeating);
+    }
+}
+
+// Frame events are where gameplay hooks in, "hit" is where attacks land (see on_attack_hit in main.rs)
+pub fn on_animation_event(trigger: On<AnimationEvent>) {
+    debug!("{:?} reached '{}' in its {:?} animation", trigger.entity, trigger.name, trigger.clip);
+}
+
+// Single frame sheets need no atlas, the sprite shows the whole image
+pub fn setup_sprite_sheets(mut sheets: ResMut<SpriteSheets>, mut layouts: ResMut<Assets<TextureAtlasLayout>>) {
+    let atlases: Vec<(String, Handle<TextureAtlasLayout>)> = sheets
+        .sheets
+        .iter()
+        .filter(|(_, sheet)| sheet.columns * sheet.rows > 1)
+        .map(|(path, sheet)| {
+            let layout = TextureAtlasLayout::from_grid(UVec2::new(sheet.frame_size.0, sheet.frame_size.1), sheet.columns, sheet.rows, None, None);
+            (path.clone(), layouts.add(layout))
+        })
+        .collect();
+    sheets.layouts.extend(atlases);
+}
+
+pub fn on_hurt_animation(trigger: On<DamageEvent>, mut animator_query: Query<&mut Animator>) {
+    if trigger.amount <= 0.0 {
+        return;
+    }
+    if let Ok(mut animator) = animator_query.get_mut(trigger.entity) {
+        animator.hurt = true;
+    }
+}
+
+// Picks the clip from the unit state: dead units die, damage hurts, a reset attack cooldown attacks, Movement walks
+pub fn animation_state_system(
+    mut query: Query<(&mut Animator, Has<Movement>, Option<&Attack>, Option<&Health>)>,
+    sheets: Res<SpriteSheets>,
+) {
+    for (mut animator, is_moving, maybe_attack, maybe_health) in query.iter_mut() {
+        let cooldown = maybe_attack.map_or(0.0, |attack| attack.current_cooldown);
+        let attacked = cooldown > animator.last_cooldown;
+        animator.last_cooldown = cooldown;
+        let hurt = std::mem::take(&mut animator.hurt);
+
+        let wanted = if maybe_health.is_some_and(Health::is_dead) {
+            AnimationClip::Die
+        } else if hurt {
+            AnimationClip::Hurt
+        } else if attacked {
+            AnimationClip::Attack
+        } else if is_moving {
+            AnimationClip::Walk
+        } else {
+            AnimationClip::Idle
+        };
+
+        // One-shot clips play out unless something more important comes up, a new hit restarts them
+        let current = animator.clip;
+        let playing_one_shot = current.priority() > 0 && !animator.finished;
+        let start = match (wanted == current, playing_one_shot) {
+            (true, _) => wanted.priority() > 0 && (hurt || attacked),
+            (false, true) => wanted.priority() > current.priority(),
+            (false, false) => current != AnimationClip::Die,
+        };
+        if !start {
+            continue;
+        }
+        let Some(def) = sheets.sheets.get(&animator.sheet).and_then(|sheet| sheet.clips.get(&wanted)) else {
+            continue;
+        };
+        animator.play(wanted, def);
+    }
+}
+
+// Advances the frames, writes them to the Sprite and fires the frame events.
+// Assembled Bobs have no Sprite of their own, their clips still run for the parts and the events
+pub fn animation_system(
+    mut query: Query<(Entity, &mut Animator, Option<&mut Sprite>)>,
+    sheets: Res<SpriteSheets>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (entity, mut animator, maybe_sprite) in query.iter_mut() {
+        let Some(def) = sheets.sheets.get(&animator.sheet).and_then(|sheet| sheet.clips.get(&animator.clip)) else {
+            continue;
+        };
+
+        let mut entered = Vec::new();
+        if !animator.started {
+            animator.started = true;
+            entered.push(0);
+        }
+        if !animator.finished {
+            let steps = animator.timer.tick(time.delta()).times_finished_this_tick() as usize;
+            for _ in 0..steps {
+                if animator.frame + 1 < def.frames.len() {
+                    animator.frame += 1;
+                } else if def.looping {
+                    animator.frame = 0;
+                } else {
+                    animator.finished = true;
+                    break;
+                }
+                entered.push(animator.frame);
+            }
+        }
+
+        for frame in entered {
+            if let Some(name) = def.events.get(&frame) {
+                commands.trigger(AnimationEvent { entity, clip: animator.clip, name: name.clone() });
+            }
+        }
+
+        // Plain images have no atlas to index into
+        let (Some(mut sprite), Some(layout)) = (maybe_sprite, sheets.layouts.get(&animator.sheet)) else {
+            continue;
+        };
+        let index = def.frames.get(animator.frame).copied().unwrap_or(0);
+        match sprite.texture_atlas.as_mut() {
+            Some(atlas) if atlas.index != index => atlas.index = index,
+            Some(_) => {},
+            None => sprite.texture_atlas = Some(TextureAtlas { layout: layout.clone(), index }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+    use std::time::Duration;
+    use crate::damage::{Damage, DamageType};
+
+    const TEST_SHEET: &str = r#"{
+        "test.png": (
+            frame_size: (10, 10),
+            columns: 4,
+            rows: 2,
+            clips: {
+                Idle: (frames: [0], fps: 1.0, looping: true),
+                Walk: (frames: [1, 2], fps: 10.0, looping: true),
+                Attack: (frames: [3, 4, 5], fps: 10.0, events: {1: "hit"}),
+                Hurt: (frames: [6], fps: 10.0),
+                Die: (frames: [6, 7], fps: 10.0),
+            },
+        ),
+    }"#;
+
+    // Frame events fired so far
+    #[derive(Resource, Default)]
+    struct Fired(Vec<(AnimationClip, String)>);
+
+    fn world() -> World {
+        let mut world = World::new();
+        let sheets = ron::from_str(TEST_SHEET).unwrap();
+        world.insert_resource(SpriteSheets { sheets, layouts: HashMap::new() });
+        world.init_resource::<Time>();
+        world.init_resource::<Fired>();
+        world.add_observer(|trigger: On<AnimationEvent>, mut fired: ResMut<Fired>| {
+            fired.0.push((trigger.clip, trigger.name.clone()));
+        });
+        world
+    }
+
+    fn pick_clip(world: &mut World, entity: Entity) -> AnimationClip {
+        world.run_system_once(animation_state_system).unwrap();
+        world.get::<Animator>(entity).unwrap().clip
+    }
+
+    fn step(world: &mut World, seconds: f32) {
+        world.resource_mut::<Time>().advance_by(Duration::from_secs_f32(seconds));
+        world.run_system_once(animation_system).unwrap();
+    }
+
+    fn play(world: &mut World, entity: Entity, clip: AnimationClip) {
+        let def = world.resource::<SpriteSheets>().sheets["test.png"].clips[&clip].clone();
+        world.get_mut::<Animator>(entity).unwrap().play(clip, &def);
+    }
+
+    fn frame(world: &World, entity: Entity) -> (usize, bool) {
+        let animator = world.get::<Animator>(entity).unwrap();
+        (animator.frame, animator.finished)
+    }
+
+    #[test]
+    fn higher_priority_clips_interrupt_lower_ones() {
+        let mut world = world();
+        let target = world.spawn_empty().id();
+        let entity = world
+            .spawn((Animator::new("test.png"), Attack::melee(target, Damage::new(1.0, DamageType::Kinetic), 1.0), Health::new(10.0)))
+            .id();
+        assert_eq!(pick_clip(&mut world, entity), AnimationClip::Idle);
+
+        // The cooldown going up means an attack just happened
+        world.get_mut::<Attack>(entity).unwrap().current_cooldown = 1.0;
+        assert_eq!(pick_clip(&mut world, entity), AnimationClip::Attack);
+
+        // Walking waits for the attack to play out
+        world.entity_mut(entity).insert(Movement::new(100.0, Vec2::ZERO));
+        world.get_mut::<Attack>(entity).unwrap().current_cooldown = 0.9;
+        assert_eq!(pick_clip(&mut world, entity), AnimationClip::Attack);
+        world.get_mut::<Animator>(entity).unwrap().finished = true;
+        assert_eq!(pick_clip(&mut world, entity), AnimationClip::Walk);
+
+        // Getting hurt cuts into an attack, attacking does not cut into getting hurt
+        world.get_mut::<Attack>(entity).unwrap().current_cooldown = 1.0;
+        assert_eq!(pick_clip(&mut world, entity), AnimationClip::Attack);
+        world.get_mut::<Animator>(entity).unwrap().hurt = true;
+        assert_eq!(pick_clip(&mut world, entity), AnimationClip::Hurt);
+        world.get_mut::<Attack>(entity).unwrap().current_cooldown = 2.0;
+        assert_eq!(pick_clip(&mut world, entity), AnimationClip::Hurt);
+
+        // Dying beats everything and never gives way
+        world.get_mut::<Health>(entity).unwrap().take_damage(10.0);
+        assert_eq!(pick_clip(&mut world, entity), AnimationClip::Die);
+        world.get_mut::<Animator>(entity).unwrap().finished = true;
+        world.get_mut::<Animator>(entity).unwrap().hurt = true;
+        assert_eq!(pick_clip(&mut world, entity), AnimationClip::Die);
+    }
+
+    #[test]
+    fn one_shot_clips_fire_their_events_once_and_hold_the_last_frame() {
+        let mut world = world();
+        let entity = world.spawn(Animator::new("test.png")).id();
+        play(&mut world, entity, AnimationClip::Attack);
+
+        step(&mut world, 0.0);
+        assert_eq!(frame(&world, entity), (0, false));
+        assert!(world.resource::<Fired>().0.is_empty());
+
+        step(&mut world, 0.1);
+        assert_eq!(frame(&world, entity), (1, false));
+        assert_eq!(world.resource::<Fired>().0, vec![(AnimationClip::Attack, "hit".to_string())]);
+
+        step(&mut world, 0.1);
+        assert_eq!(frame(&world, entity), (2, false));
+        step(&mut world, 0.5);
+        assert_eq!(frame(&world, entity), (2, true));
+        assert_eq!(world.resource::<Fired>().0.len(), 1);
+    }
+
+    #[test]
+    fn looping_clips_wrap_around() {
+        let mut world = world();
+        let entity = world.spawn(Animator::new("test.png")).id();
+        play(&mut world, entity, AnimationClip::Walk);
+
+        step(&mut world, 0.0);
+        step(&mut world, 0.1);
+        assert_eq!(frame(&world, entity), (1, false));
+        step(&mut world, 0.1);
+        assert_eq!(frame(&world, entity), (0, false));
+    }
+
+    #[test]
+    fn shipped_sheets_stay_inside_their_images() {
+        let sheets = SpriteSheets::default();
+        assert!(!sheets.sheets.is_empty());
+        for sheet in sheets.sheets.values() {
+            let frame_count = (sheet.columns * sheet.rows) as usize;
+            for clip in sheet.clips.values() {
+                assert!(clip.frames.iter().all(|frame| *frame < frame_count));
+                assert!(clip.events.keys().all(|index| *index < clip.frames.len()));
+            }
+        }
+
+        // The raider strikes in the middle of its swing
+        let attack = &sheets.sheets["sprites/Raider.png"].clips[&AnimationClip::Attack];
+        let hit = attack.events.iter().find(|(_, name)| *name == "hit").map(|(index, _)| *index);
+        assert!(hit.is_some_and(|index| index > 0 && index + 1 < attack.frames.len()));
+    }
+}
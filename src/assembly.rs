@@ -0,0 +1,87 @@
+use bevy::prelude::*;
+use crate::animation::{AnimationClip, Animator};
+use crate::loot::LootType;
+use crate::{Arms, ArmsKind, Body, Bob, Head, Legs};
+
+const PART_COLOR: Color = Color::srgb(0.7, 0.7, 0.7); // scrap grey
+
+// Where each part sits on a 100x100 Bob, laid out like the slots of the builder panel.
+// (part, side, offset from the Bob centre, size), side is -1 for the left, 1 for the right and 0 for the middle
+const PART_LAYOUT: [(LootType, f32, Vec2, Vec2); 6] = [
+    (LootType::Head, 0.0, Vec2::new(0.0, 36.0), Vec2::new(26.0, 26.0)),
+    (LootType::Body, 0.0, Vec2::new(0.0, 5.0), Vec2::new(26.0, 36.0)),
+    (LootType::Arms, -1.0, Vec2::new(-25.0, 16.0), Vec2::new(24.0, 10.0)),
+    (LootType::Arms, 1.0, Vec2::new(25.0, 16.0), Vec2::new(24.0, 10.0)),
+    (LootType::Legs, -1.0, Vec2::new(-7.0, -29.0), Vec2::new(10.0, 32.0)),
+    (LootType::Legs, 1.0, Vec2::new(7.0, -29.0), Vec2::new(10.0, 32.0)),
+];
+
+// One part sprite of an assembled Bob
+#[derive(Component)]
+pub struct BobPart {
+    pub kind: LootType,
+    side: f32,
+    rest: Vec2, // offset from the Bob centre while standing still
+}
+
+// The arms show what they do, the rest of the Bob is plain scrap
+fn arms_color(arms: ArmsKind) -> Color {
+    match arms {
+        ArmsKind::Melee => Color::srgb(0.85, 0.3, 0.25),
+        ArmsKind::Ranged => Color::srgb(0.95, 0.85, 0.2),
+        ArmsKind::Cryo => Color::srgb(0.4, 0.85, 1.0),
+    }
+}
+
+/// Spawns the part sprites of a Bob as its children, the same parts the player put into the builder
+pub fn spawn_bob_parts(parent: &mut ChildSpawnerCommands, arms: ArmsKind) {
+    for (kind, side, offset, size) in PART_LAYOUT {
+        let color = match kind {
+            LootType::Arms => arms_color(arms).mix(&PART_COLOR, 0.3),
+            _ => PART_COLOR,
+        };
+        let mut part = parent.spawn((
+            BobPart { kind, side, rest: offset },
+            Sprite::from_color(color, size),
+            Transform::from_translation(offset.extend(0.1)), // just above the Bob itself
+        ));
+        match kind {
+            LootType::Head => { part.insert((Head, Name::new("Head"))); },
+            LootType::Body => { part.insert((Body, Name::new("Body"))); },
+            LootType::Arms => { part.insert((Arms, Name::new("Arm"))); },
+            LootType::Legs => { part.insert((Legs, Name::new("Leg"))); },
+        }
+    }
+}
+
+// Moves the parts with the Bob's animation clip: legs and arms swing while walking, arms go up to attack,
+// the whole Bob shakes when hurt and the head drops when it dies
+pub fn bob_part_animation_system(
+    bob_query: Query<(&Animator, &Children), With<Bob>>,
+    mut part_query: Query<(&BobPart, &mut Transform)>,
+    time: Res<Time>,
+) {
+    let swing = (time.elapsed_secs() * 10.0).sin();
+    let shake = (time.elapsed_secs() * 60.0).sin();
+
+    for (animator, children) in bob_query.iter() {
+        let clip = animator.clip();
+        for child in children.iter() {
+            let Ok((part, mut transform)) = part_query.get_mut(child) else {
+                continue;
+            };
+            let offset = match (clip, part.kind) {
+                (AnimationClip::Walk, LootType::Legs) => Vec2::Y * swing * 4.0 * part.side,
+                (AnimationClip::Walk, LootType::Arms) => Vec2::Y * swing * -2.0 * part.side,
+                (AnimationClip::Attack, LootType::Arms) => Vec2::Y * 6.0,
+                (AnimationClip::Hurt, _) => Vec2::X * shake * 2.0,
+                (AnimationClip::Die, LootType::Head) => Vec2::new(6.0, -10.0),
+                _ => Vec2::ZERO,
+            };
+            let position = part.rest + offset;
+            if transform.translation.xy() != position {
+                transform.translation = position.extend(transform.translation.z);
+            }
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use bevy::{audio::Volume, prelude::*};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::damage::DamageEvent;
+use crate::GameStates;
+
+const AUDIO_RON: &str = include_str!("../assets/data/audio.ron");
+const SETTINGS_PATH: &str = "settings.ron"; // next to wherever the game is started from
+const MIN_REPEAT_SECONDS: f64 = 0.05; // the same effect is not started again sooner, so a volley of hits is one sound
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SoundEffect {
+    Click,
+    PlacePart,
+    BuildBob,
+    Hit,
+    Loot,
+    Win,
+    Loss,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+struct AudioDefs {
+    music: HashMap<GameStates, String>,
+    effects: HashMap<SoundEffect, String>,
+}
+
+/// Music and sound effects from `assets/data/audio.ron`, loaded by `setup_audio`
+#[derive(Resource)]
+pub struct AudioLibrary {
+    defs: AudioDefs,
+    music: HashMap<GameStates, Handle<AudioSource>>,
+    effects: HashMap<SoundEffect, Handle<AudioSource>>,
+    last_played: HashMap<SoundEffect, f64>,
+}
+
+impl Default for AudioLibrary {
+    fn default() -> Self {
+        let defs = ron::from_str(AUDIO_RON).unwrap_or_else(|error| {
+            error!("Could not read audio.ron: {}", error);
+            AudioDefs::default()
+        });
+        Self { defs, music: HashMap::new(), effects: HashMap::new(), last_played: HashMap::new() }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VolumeChannel {
+    Master,
+    Music,
+    Effects,
+}
+
+impl VolumeChannel {
+    pub const ALL: [VolumeChannel; 3] = [VolumeChannel::Master, VolumeChannel::Music, VolumeChannel::Effects];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            VolumeChannel::Master => "Master",
+            VolumeChannel::Music => "Music",
+            VolumeChannel::Effects => "Effects",
+        }
+    }
+}
+
+/// Volumes from 0.0 to 1.0, saved to `settings.ron` so they carry over to the next run
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct VolumeSettings {
+    pub master: f32,
+    pub music: f32,
+    pub effects: f32,
+}
+
+impl Default for VolumeSettings {
+    fn default() -> Self {
+        Self { master: 0.8, music: 0.6, effects: 0.8 }
+    }
+}
+
+impl VolumeSettings {
+    // Falls back to the defaults when there is no settings file yet
+    pub fn load() -> Self {
+        let Ok(text) = std::fs::read_to_string(SETTINGS_PATH) else {
+            return Self::default();
+        };
+        ron::from_str(&text).unwrap_or_else(|error| {
+            warn!("Could not read {}, using the default volumes: {}", SETTINGS_PATH, error);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self) {
+        let result = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|error| error.to_string())
+            .and_then(|text| std::fs::write(SETTINGS_PATH, text).map_err(|error| error.to_string()));
+        if let Err(error) = result {
+            warn!("Could not save {}: {}", SETTINGS_PATH, error);
+        }
+    }
+
+    pub fn get(&self, channel: VolumeChannel) -> f32 {
+        match channel {
+            VolumeChannel::Master => self.master,
+            VolumeChannel::Music => self.music,
+            VolumeChannel::Effects => self.effects,
+        }
+    }
+
+    pub fn set(&mut self, channel: VolumeChannel, volume: f32) {
+        let volume = volume.clamp(0.0, 1.0);
+        match channel {
+            VolumeChannel::Master => self.master = volume,
+            VolumeChannel::Music => self.music = volume,
+            VolumeChannel::Effects => self.effects = volume,
+        }
+    }
+
+    fn music_volume(&self) -> Volume {
+        Volume::Linear(self.master * self.music)
+    }
+
+    fn effects_volume(&self) -> Volume {
+        Volume::Linear(self.master * self.effects)
+    }
+}
+
+#[derive(Event)]
+pub struct PlaySoundEvent(pub SoundEffect);
+
+pub trait SoundCommands {
+    fn play_sound(&mut self, effect: SoundEffect);
+}
+
+impl SoundCommands for Commands<'_, '_> {
+    fn play_sound(&mut self, effect: SoundEffect) {
+        self.trigger(PlaySoundEvent(effect));
+    }
+}
+
+// The track that is currently playing, there is only ever one
+#[derive(Component)]
+pub struct Music;
+
+// Missing files are reported once by the asset server and play as silence
+pub fn setup_audio(mut library: ResMut<AudioLibrary>, asset_server: Res<AssetServer>) {
+    let music = library.defs.music.iter().map(|(state, path)| (*state, asset_server.load(path.clone()))).collect();
+    let effects = library.defs.effects.iter().map(|(effect, path)| (*effect, asset_server.load(path.clone()))).collect();
+    library.music = music;
+    library.effects = effects;
+}
+
+pub fn on_play_sound(
+    trigger: On<PlaySoundEvent>,
+    mut library: ResMut<AudioLibrary>,
+    settings: Res<VolumeSettings>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    let effect = trigger.0;
+    let Some(handle) = library.effects.get(&effect).cloned() else {
+        return;
+    };
+    let now = time.elapsed_secs_f64();
+    if library.last_played.get(&effect).is_some_and(|at| now - at < MIN_REPEAT_SECONDS) {
+        return;
+    }
+    library.last_played.insert(effect, now);
+
+    commands.spawn((
+        AudioPlayer(handle),
+        PlaybackSettings::DESPAWN.with_volume(settings.effects_volume()),
+        Name::new("Sound Effect"),
+    ));
+}
+
+pub fn on_hit_sound(trigger: On<DamageEvent>, mut commands: Commands) {
+    if trigger.amount > 0.0 {
+        commands.play_sound(SoundEffect::Hit);
+    }
+}
+
+// Every button clicks, whatever it does
+pub fn button_sound_system(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<Button>)>,
+    mut commands: Commands,
+) {
+    if interaction_query.iter().any(|interaction| *interaction == Interaction::Pressed) {
+        commands.play_sound(SoundEffect::Click);
+    }
+}
+
+// Switches to the music of the new game state and plays the win or loss sting
+pub fn state_audio_system(
+    state: Res<State<GameStates>>,
+    music_query: Query<Entity, With<Music>>,
+    library: Res<AudioLibrary>,
+    settings: Res<VolumeSettings>,
+    mut commands: Commands,
+) {
+    for music in music_query.iter() {
+        commands.entity(music).despawn();
+    }
+    if let Some(handle) = library.music.get(state.get()) {
+        commands.spawn((
+            Music,
+            AudioPlayer(handle.clone()),
+            PlaybackSettings::LOOP.with_volume(settings.music_volume()),
+            Name::new("Music"),
+        ));
+    }
+
+    match state.get() {
+        GameStates::Win => commands.play_sound(SoundEffect::Win),
+        GameStates::Loss => commands.play_sound(SoundEffect::Loss),
+        GameStates::ChoosingDifficulty | GameStates::Playing => {},
+    }
+}
+
+// Effects are short, only the music follows volume changes while it plays
+pub fn music_volume_system(
+    settings: Res<VolumeSettings>,
+    mut sink_query: Query<&mut AudioSink, With<Music>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    for mut sink in sink_query.iter_mut() {
+        sink.set_volume(settings.music_volume());
+    }
+}
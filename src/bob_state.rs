@@ -0,0 +1,312 @@
+use bevy::ecs::query::QueryData;
+use bevy::prelude::*;
+use crate::formation::Formation;
+use crate::map::ScoutSite;
+use crate::repair::BaseRepair;
+use crate::message_log::GameLogCommands;
+use crate::{AtTarget, Attack, Bob, Health, Movement, Scout};
+
+const DEAD_BOB_DESPAWN_SECONDS: f32 = 3.0;
+const DEAD_BOB_TINT: Color = Color::srgb(0.3, 0.3, 0.3);
+const BOB_REPAIR_PER_SECOND: f32 = 5.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BobState {
+    Attacking,
+    Idling,
+    Scouting,
+    Returning, // walking back to the formation after a mission
+    Repairing, // fixing itself or the home base up, standing at the base
+    Moving,    // walking to the spot of a move order and holding it
+    Dead,
+}
+
+// Every BobState has a matching component, the component a Bob carries is its state.
+// Systems filter with With<...> and observers on Add/Remove act as the enter/exit hooks of the state.
+#[derive(Component)]
+pub struct Idling;
+
+#[derive(Component)]
+pub struct Attacking;
+
+#[derive(Component)]
+pub struct Scouting;
+
+#[derive(Component)]
+pub struct Returning;
+
+#[derive(Component)]
+pub struct Repairing;
+
+#[derive(Component)]
+pub struct Moving;
+
+#[derive(Component)]
+pub struct Dead(Timer);
+
+// Where a Moving Bob was ordered to go
+#[derive(Component)]
+pub struct MoveOrder(pub Vec2);
+
+// The enemy an Attacking Bob was ordered to go after instead of the nearest one
+#[derive(Component)]
+pub struct AttackOrder(pub Entity);
+
+// Reads which state component a Bob carries, for systems that branch on the state
+#[derive(QueryData)]
+pub struct CurrentBobState {
+    attacking: Has<Attacking>,
+    idling: Has<Idling>,
+    scouting: Has<Scouting>,
+    returning: Has<Returning>,
+    repairing: Has<Repairing>,
+    moving: Has<Moving>,
+    dead: Has<Dead>,
+}
+
+impl CurrentBobStateItem<'_, '_> {
+    /// None for entities that are not Bobs
+    pub fn get(&self) -> Option<BobState> {
+        [
+            (self.attacking, BobState::Attacking),
+            (self.idling, BobState::Idling),
+            (self.scouting, BobState::Scouting),
+            (self.returning, BobState::Returning),
+            (self.repairing, BobState::Repairing),
+            (self.moving, BobState::Moving),
+            (self.dead, BobState::Dead),
+        ]
+        .into_iter()
+        .find_map(|(has, state)| has.then_some(state))
+    }
+}
+
+/// Changes the state of a Bob. Always go through this instead of inserting or removing the state components
+/// so the old state component is removed (exit hook) before the new one is added (enter hook).
+pub trait BobStateCommands {
+    fn set_bob_state(&mut self, state: BobState) -> &mut Self;
+}
+
+impl BobStateCommands for EntityCommands<'_> {
+    fn set_bob_state(&mut self, state: BobState) -> &mut Self {
+        self.queue(move |mut entity: EntityWorldMut| transition(&mut entity, state))
+    }
+}
+
+fn transition(entity: &mut EntityWorldMut, next: BobState) {
+    if !entity.contains::<Bob>() {
+        return;
+    }
+    // world.query registers the state components that were never inserted yet
+    let id = entity.id();
+    let Some(previous) = entity.world_scope(|world| {
+        world.query::<CurrentBobState>().get(world, id).ok().and_then(|state| state.get())
+    }) else {
+        return;
+    };
+    if previous == next {
+        return;
+    }
+
+    // Every state starts standing still, the state's own logic decides where to walk next
+    entity.remove::<(Movement, AtTarget)>();
+
+    match previous {
+        BobState::Attacking => { entity.remove::<Attacking>(); },
+        BobState::Idling => { entity.remove::<Idling>(); },
+        BobState::Scouting => { entity.remove::<Scouting>(); },
+        BobState::Returning => { entity.remove::<Returning>(); },
+        BobState::Repairing => { entity.remove::<Repairing>(); },
+        BobState::Moving => { entity.remove::<Moving>(); },
+        BobState::Dead => { entity.remove::<Dead>(); },
+    }
+
+    insert_state_component(entity, next);
+}
+
+fn insert_state_component(entity: &mut EntityWorldMut, state: BobState) {
+    match state {
+        BobState::Attacking => { entity.insert(Attacking); },
+        BobState::Idling => { entity.insert(Idling); },
+        BobState::Scouting => { entity.insert(Scouting); },
+        BobState::Returning => { entity.insert(Returning); },
+        BobState::Repairing => { entity.insert(Repairing); },
+        BobState::Moving => { entity.insert(Moving); },
+        BobState::Dead => { entity.insert(Dead(Timer::from_seconds(DEAD_BOB_DESPAWN_SECONDS, TimerMode::Once))); },
+    }
+}
+
+// Idling: take a slot in the formation on enter, give it back on exit
+pub fn on_enter_idling(
+    trigger: On<Add, Idling>,
+    mut formation: ResMut<Formation>,
+) {
+    formation.join(trigger.entity);
+}
+
+pub fn on_exit_idling(
+    trigger: On<Remove, Idling>,
+    mut formation: ResMut<Formation>,
+) {
+    formation.leave(trigger.entity);
+}
+
+// Attacking: stop hitting the enemy when leaving the state
+pub fn on_exit_attacking(
+    trigger: On<Remove, Attacking>,
+    mut commands: Commands,
+) {
+    if let Ok(mut entity) = commands.get_entity(trigger.entity) {
+        entity.try_remove::<(Attack, AttackOrder)>();
+    }
+}
+
+// Moving: the order is done once the Bob is given something else to do
+pub fn on_exit_moving(
+    trigger: On<Remove, Moving>,
+    mut commands: Commands,
+) {
+    if let Ok(mut entity) = commands.get_entity(trigger.entity) {
+        entity.try_remove::<MoveOrder>();
+    }
+}
+
+// Scouting: the Scout marker only lives while the Bob is out scouting
+pub fn on_exit_scouting(
+    trigger: On<Remove, Scouting>,
+    mut commands: Commands,
+) {
+    if let Ok(mut entity) = commands.get_entity(trigger.entity) {
+        entity.try_remove::<(Scout, ScoutSite)>();
+    }
+}
+
+// Dead: drop whatever the Bob was doing and grey it out until it is cleaned up
+pub fn on_enter_dead(
+    trigger: On<Add, Dead>,
+    mut sprite_query: Query<&mut Sprite>,
+    children_query: Query<&Children>,
+    mut commands: Commands,
+) {
+    commands.entity(trigger.entity).remove::<(Attack, Scout)>();
+    // Assembled Bobs are drawn by their part sprites
+    let parts = std::iter::once(trigger.entity).chain(children_query.iter_descendants(trigger.entity));
+    for part in parts {
+        if let Ok(mut sprite) = sprite_query.get_mut(part) {
+            sprite.color = DEAD_BOB_TINT;
+        }
+    }
+    commands.log_error("A Bob was destroyed!");
+}
+
+// Moves Bobs into Dead when their health runs out and into Repairing when an idle Bob is damaged
+pub fn bob_health_system(
+    query: Query<(Entity, &Health, Has<Idling>, Has<Dead>), With<Bob>>,
+    mut commands: Commands,
+) {
+    for (entity, health, is_idling, is_dead) in query.iter() {
+        if health.is_dead() && !is_dead {
+            commands.entity(entity).set_bob_state(BobState::Dead);
+        } else if is_idling && health.current < health.max {
+            commands.entity(entity).set_bob_state(BobState::Repairing);
+        }
+    }
+}
+
+// Repairing Bobs heal once they stand at the home base and head back to the formation
+// when they are fixed up and there are no base repairs left to help with
+pub fn bob_repair_system(
+    mut query: Query<(Entity, &mut Health), (With<Repairing>, With<AtTarget>)>,
+    base_repair: Res<BaseRepair>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (entity, mut health) in query.iter_mut() {
+        health.current = (health.current + BOB_REPAIR_PER_SECOND * time.delta_secs()).min(health.max);
+        if health.current >= health.max && !base_repair.is_repairing() {
+            commands.entity(entity).set_bob_state(BobState::Returning);
+        }
+    }
+}
+
+pub fn dead_bob_system(
+    mut query: Query<(Entity, &mut Dead)>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (entity, mut dead) in query.iter_mut() {
+        dead.0.tick(time.delta());
+        if dead.0.is_finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.init_resource::<Formation>();
+        world.add_observer(on_enter_idling);
+        world.add_observer(on_exit_idling);
+        world
+    }
+
+    fn set_state(world: &mut World, entity: Entity, state: BobState) {
+        world.commands().entity(entity).set_bob_state(state);
+        world.flush();
+    }
+
+    fn state(world: &mut World, entity: Entity) -> Option<BobState> {
+        world.query::<CurrentBobState>().get(world, entity).unwrap().get()
+    }
+
+    #[test]
+    fn transition_swaps_the_state_component() {
+        let mut world = world();
+        let entity = world.spawn((Bob, Idling)).id();
+        assert_eq!(state(&mut world, entity), Some(BobState::Idling));
+
+        set_state(&mut world, entity, BobState::Attacking);
+        assert_eq!(state(&mut world, entity), Some(BobState::Attacking));
+        assert!(!world.entity(entity).contains::<Idling>());
+    }
+
+    #[test]
+    fn hooks_run_on_enter_and_exit() {
+        let mut world = world();
+        let entity = world.spawn((Bob, Idling)).id();
+        assert_eq!(world.resource::<Formation>().slot_of(entity), Some(0));
+
+        set_state(&mut world, entity, BobState::Scouting);
+        assert_eq!(world.resource::<Formation>().slot_of(entity), None);
+
+        set_state(&mut world, entity, BobState::Idling);
+        assert_eq!(world.resource::<Formation>().slot_of(entity), Some(0));
+    }
+
+    #[test]
+    fn transition_to_the_same_state_keeps_movement() {
+        let mut world = world();
+        let entity = world.spawn((Bob, Moving, AtTarget(Vec2::ZERO))).id();
+
+        set_state(&mut world, entity, BobState::Moving);
+        assert!(world.entity(entity).contains::<AtTarget>());
+
+        set_state(&mut world, entity, BobState::Returning);
+        assert!(!world.entity(entity).contains::<AtTarget>());
+    }
+
+    #[test]
+    fn entities_without_a_state_are_not_bobs() {
+        let mut world = world();
+        let entity = world.spawn_empty().id();
+        assert_eq!(state(&mut world, entity), None);
+
+        // Only Bobs change state
+        set_state(&mut world, entity, BobState::Attacking);
+        assert_eq!(state(&mut world, entity), None);
+    }
+}
@@ -0,0 +1,178 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+use crate::collision::SpatialQuery;
+use crate::damage::{Damage, DamageType};
+use crate::director::Director;
+use crate::enemies::{spawn_enemy, Boss, EnemyRoster, EnemyStats};
+use crate::floating_text::{FloatingTextCommands, FloatingTextStyle, TextPopup};
+use crate::message_log::GameLogCommands;
+use crate::{deal_damage, Attack, Bob, DamageTarget, Difficulty, Enemy, GameStates, Health, Movement, Size};
+
+const PHASE_TEXT_COLOR: Color = Color::srgb(1.0, 0.2, 0.6);
+const ADD_SPACING: f32 = 90.0; // gap between adds spawned by a phase
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AreaAttackDef {
+    pub damage: f32,
+    pub damage_type: DamageType,
+    pub radius: f32, // gap between the hitboxes
+    pub interval: f32,
+}
+
+fn one() -> f32 {
+    1.0
+}
+
+/// Entered once the boss health drops to `threshold` (fraction of max health)
+#[derive(Deserialize, Debug, Clone)]
+pub struct BossPhaseDef {
+    pub threshold: f32,
+    pub name: String,
+    #[serde(default = "one")]
+    pub damage_multiplier: f32,
+    #[serde(default = "one")]
+    pub cooldown_multiplier: f32, // below 1.0 attacks come faster
+    #[serde(default = "one")]
+    pub speed_multiplier: f32,
+    #[serde(default)]
+    pub summon: Vec<String>, // enemy ids spawned around the boss when the phase starts
+    #[serde(default)]
+    pub area_attack: Option<AreaAttackDef>,
+}
+
+// The phases of a boss in threshold order, `current` is how many of them already started
+#[derive(Component)]
+pub struct BossPhases {
+    phases: Vec<BossPhaseDef>,
+    current: usize,
+}
+
+impl BossPhases {
+    pub fn new(mut phases: Vec<BossPhaseDef>) -> Self {
+        phases.sort_by(|a, b| b.threshold.total_cmp(&a.threshold));
+        Self { phases, current: 0 }
+    }
+
+    // 1 before the first threshold was crossed
+    pub fn number(&self) -> usize {
+        self.current + 1
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.current.checked_sub(1).and_then(|index| self.phases.get(index)).map(|phase| phase.name.as_str())
+    }
+
+    // The next phase if the health fraction has dropped far enough for it
+    fn next_due(&self, health_fraction: f32) -> Option<&BossPhaseDef> {
+        self.phases.get(self.current).filter(|phase| health_fraction <= phase.threshold)
+    }
+}
+
+// Hits every Bob around the boss at once
+#[derive(Component)]
+pub struct AreaAttack {
+    timer: Timer,
+    radius: f32,
+    damage: Damage,
+}
+
+// Starts every phase whose threshold the boss health went under
+pub fn boss_phase_system(
+    mut boss_query: Query<(
+        Entity,
+        &mut BossPhases,
+        &mut EnemyStats,
+        Option<&mut Attack>,
+        Option<&mut Movement>,
+        &Health,
+        &Transform,
+        &Size,
+    ), With<Boss>>,
+    minion_query: Query<(), (With<Enemy>, Without<Boss>)>,
+    director: Res<Director>,
+    roster: Res<EnemyRoster>,
+    difficulty: Res<Difficulty>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    for (entity, mut phases, mut stats, mut maybe_attack, mut maybe_movement, health, transform, size) in boss_query.iter_mut() {
+        if health.is_dead() {
+            continue;
+        }
+        let health_fraction = health.current / health.max.max(1.0);
+
+        while let Some(phase) = phases.next_due(health_fraction).cloned() {
+            phases.current += 1;
+
+            stats.speed *= phase.speed_multiplier;
+            if let Some(movement) = maybe_movement.as_mut() {
+                movement.speed = stats.speed;
+            }
+            if let Some(attack) = stats.attack.as_mut() {
+                attack.damage *= phase.damage_multiplier;
+                attack.cooldown *= phase.cooldown_multiplier;
+            }
+            // The running attack picks the changes up right away
+            if let Some(attack) = maybe_attack.as_mut() {
+                attack.damage.amount *= phase.damage_multiplier;
+                attack.max_cooldown *= phase.cooldown_multiplier;
+                attack.current_cooldown = attack.current_cooldown.min(attack.max_cooldown);
+            }
+
+            if let Some(area) = &phase.area_attack {
+                commands.entity(entity).insert(AreaAttack {
+                    timer: Timer::from_seconds(area.interval, TimerMode::Repeating),
+                    radius: area.radius,
+                    damage: Damage::new(difficulty.enemy_attack_damage(area.damage), area.damage_type),
+                });
+            }
+
+            // Adds count towards the director's cap like every other enemy, the ones that don't fit stay home
+            let room = director.alive_cap.saturating_sub(minion_query.iter().count());
+            let summon = &phase.summon[..phase.summon.len().min(room)];
+            let first_x = -(summon.len() as f32 - 1.0) * ADD_SPACING / 2.0;
+            for (index, id) in summon.iter().enumerate() {
+                let Some(def) = roster.get(id) else {
+                    commands.log_error(format!("Unknown boss add '{}'", id));
+                    continue;
+                };
+                // Line the adds up just below the boss
+                let offset = Vec2::new(first_x + index as f32 * ADD_SPACING, -(size.0.y + def.size.1) / 2.0 - ADD_SPACING / 2.0);
+                spawn_enemy(&mut commands, def, transform.translation.xy() + offset, &difficulty, &asset_server);
+            }
+
+            let announcement = format!("PHASE {}: {}!", phases.number(), phase.name.to_uppercase());
+            let position = transform.translation + Vec3::Y * size.0.y / 2.0;
+            commands.floating_text(TextPopup::new(announcement.clone(), PHASE_TEXT_COLOR, position).with_style(FloatingTextStyle::ANNOUNCEMENT));
+            commands.log_warning(format!("The boss entered {}", announcement.trim_end_matches('!').to_lowercase()));
+        }
+    }
+}
+
+// Bosses with an area attack periodically damage every Bob close to them
+pub fn area_attack_system(
+    mut boss_query: Query<(Entity, &mut AreaAttack, &Transform, &Health), Without<Bob>>,
+    mut bob_query: Query<DamageTarget, With<Bob>>,
+    spatial: SpatialQuery,
+    time: Res<Time>,
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<GameStates>>,
+) {
+    for (entity, mut area, transform, health) in boss_query.iter_mut() {
+        if health.is_dead() || !area.timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+        let Some(hitbox) = spatial.hitbox(entity) else {
+            continue;
+        };
+
+        commands.floating_text(TextPopup::new("SHOCKWAVE!", PHASE_TEXT_COLOR, transform.translation).over(entity));
+        for (bob, _) in spatial.within(hitbox, area.radius) {
+            if let Ok(mut target) = bob_query.get_mut(bob)
+                && !target.health.is_dead()
+            {
+                deal_damage(&mut commands, &mut next_state, &mut target, area.damage, entity);
+            }
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use bevy::{camera::ScalingMode, ecs::system::SystemParam, input::mouse::AccumulatedMouseScroll, picking::hover::HoverMap, prelude::*};
+use crate::formation::Formation;
+use crate::map::BattleMap;
+use crate::bob_state::Dead;
+use crate::{Bob, Selected};
+
+/// World units the camera shows at zoom 1.0, whatever the window size. The shorter side is stretched to keep the aspect ratio
+pub const LOGICAL_RESOLUTION: Vec2 = Vec2::new(1280.0, 720.0);
+const PAN_SPEED: f32 = 600.0; // world units per second at zoom 1.0
+const EDGE_PAN_MARGIN: f32 = 20.0; // pixels from the window edge that start panning
+const MIN_ZOOM: f32 = 0.5; // projection scale, lower is closer
+const MAX_ZOOM: f32 = 2.0;
+const ZOOM_STEP: f32 = 0.1;
+const FOLLOW_SMOOTHING: f32 = 5.0; // higher catches up faster
+const DOUBLE_CLICK_SECONDS: f64 = 0.3;
+
+/// Player controlled camera: pans with WASD, the arrow keys or the window edges, zooms with the scroll wheel
+#[derive(Component, Default)]
+pub struct CameraController {
+    pub follow: Option<Entity>,
+    last_click: Option<(Entity, f64)>,
+}
+
+/// Fills the view behind everything, resized by `background_system`
+#[derive(Component)]
+pub struct Background;
+
+// The window and whether the pointer is over a UI node, in which case the camera leaves the mouse to the UI
+#[derive(SystemParam)]
+pub struct CameraCursor<'w, 's> {
+    q_window: Query<'w, 's, &'static Window>,
+    hover_map: Res<'w, HoverMap>,
+    node_query: Query<'w, 's, (), With<Node>>,
+}
+
+impl CameraCursor<'_, '_> {
+    fn window(&self) -> Option<&Window> {
+        self.q_window.single().ok()
+    }
+
+    fn over_ui(&self) -> bool {
+        self.hover_map.values().flat_map(|hovered| hovered.keys()).any(|entity| self.node_query.contains(*entity))
+    }
+}
+
+// Projection showing the logical resolution, scaled to fit the window
+pub fn logical_projection() -> Projection {
+    Projection::Orthographic(OrthographicProjection {
+        scaling_mode: ScalingMode::AutoMin { min_width: LOGICAL_RESOLUTION.x, min_height: LOGICAL_RESOLUTION.y },
+        ..OrthographicProjection::default_2d()
+    })
+}
+
+// Zoom level and half the world area the camera sees
+fn view(projection: &Projection) -> (f32, Vec2) {
+    match projection {
+        Projection::Orthographic(ortho) => (ortho.scale, ortho.area.half_size()),
+        _ => (1.0, LOGICAL_RESOLUTION / 2.0),
+    }
+}
+
+// Keeps the view inside the map, centred on any axis where the view is bigger than the map
+fn clamp_to_bounds(position: Vec2, half_view: Vec2, bounds: Rect) -> Vec2 {
+    let min = bounds.min + half_view;
+    let max = bounds.max - half_view;
+    let center = bounds.center();
+    Vec2::new(
+        if min.x <= max.x { position.x.clamp(min.x, max.x) } else { center.x },
+        if min.y <= max.y { position.y.clamp(min.y, max.y) } else { center.y },
+    )
+}
+
+pub fn camera_pan_system(
+    keys: Res<ButtonInput<KeyCode>>,
+    pointer: CameraCursor,
+    mut camera_query: Query<(&mut Transform, &mut CameraController, &Projection)>,
+    bob_query: Query<(&Transform, Has<Dead>), (With<Bob>, Without<CameraController>)>,
+    map: Res<BattleMap>,
+    time: Res<Time>,
+) {
+    let Some(window) = pointer.window() else {
+        return;
+    };
+    let Ok((mut transform, mut controller, projection)) = camera_query.single_mut() else {
+        return;
+    };
+    let (zoom, half_view) = view(projection);
+
+    let mut direction = Vec2::ZERO;
+    for (key, alternative, step) in [
+        (KeyCode::KeyW, KeyCode::ArrowUp, Vec2::Y),
+        (KeyCode::KeyS, KeyCode::ArrowDown, Vec2::NEG_Y),
+        (KeyCode::KeyA, KeyCode::ArrowLeft, Vec2::NEG_X),
+        (KeyCode::KeyD, KeyCode::ArrowRight, Vec2::X),
+    ] {
+        if keys.any_pressed([key, alternative]) {
+            direction += step;
+        }
+    }
+    // Window coordinates grow downwards
+    if let Some(cursor) = window.cursor_position().filter(|_| !pointer.over_ui()) {
+        if cursor.x < EDGE_PAN_MARGIN { direction.x -= 1.0; }
+        if cursor.x > window.width() - EDGE_PAN_MARGIN { direction.x += 1.0; }
+        if cursor.y < EDGE_PAN_MARGIN { direction.y += 1.0; }
+        if cursor.y > window.height() - EDGE_PAN_MARGIN { direction.y -= 1.0; }
+    }
+
+    let mut position = transform.translation.xy();
+    if direction != Vec2::ZERO {
+        // Panning by hand stops following
+        controller.follow = None;
+        position += direction.normalize() * PAN_SPEED * zoom * time.delta_secs();
+    } else if let Some(target) = controller.follow {
+        match bob_query.get(target) {
+            Ok((bob_transform, false)) => {
+                let smoothing = (FOLLOW_SMOOTHING * time.delta_secs()).min(1.0);
+                position = position.lerp(bob_transform.translation.xy(), smoothing);
+            },
+            _ => controller.follow = None,
+        }
+    }
+
+    let clamped = clamp_to_bounds(position, half_view, map.bounds);
+    if clamped != transform.translation.xy() {
+        transform.translation = clamped.extend(transform.translation.z);
+    }
+}
+
+// Scrolling zooms unless the cursor is over the UI or the formation, which scroll themselves
+pub fn camera_zoom_system(
+    mouse_scroll: Res<AccumulatedMouseScroll>,
+    pointer: CameraCursor,
+    formation: Res<Formation>,
+    mut camera_query: Query<(&Camera, &GlobalTransform, &mut Projection), With<CameraController>>,
+) {
+    if mouse_scroll.delta.y == 0.0 || pointer.over_ui() {
+        return;
+    }
+    let Some(window) = pointer.window() else {
+        return;
+    };
+    let Ok((camera, camera_transform, mut projection)) = camera_query.single_mut() else {
+        return;
+    };
+    let over_formation = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())
+        .is_some_and(|cursor_world| formation.bounds().contains(cursor_world));
+    if over_formation {
+        return;
+    }
+
+    if let Projection::Orthographic(ortho) = &mut *projection {
+        // Scrolling up zooms in
+        ortho.scale = (ortho.scale - mouse_scroll.delta.y.signum() * ZOOM_STEP).clamp(MIN_ZOOM, MAX_ZOOM);
+    }
+}
+
+// Keeps the background covering the map and whatever the camera shows beyond it
+pub fn background_system(
+    camera_query: Query<(&Transform, &Projection), With<CameraController>>,
+    mut background_query: Query<(&mut Sprite, &mut Transform), (With<Background>, Without<CameraController>)>,
+    map: Res<BattleMap>,
+) {
+    let Ok((camera_transform, projection)) = camera_query.single() else {
+        return;
+    };
+    let center = map.bounds.center();
+    let reach = (camera_transform.translation.xy() - center).abs() + view(projection).1;
+    let size = map.bounds.size().max(reach * 2.0);
+
+    for (mut sprite, mut transform) in background_query.iter_mut() {
+        if sprite.custom_size != Some(size) {
+            sprite.custom_size = Some(size);
+        }
+        if transform.translation.xy() != center {
+            transform.translation = center.extend(transform.translation.z);
+        }
+    }
+}
+
+// Clicking a Bob selects it, clicking it again quickly makes the camera follow it
+pub fn on_bob_clicked(
+    trigger: On<Pointer<Click>>,
+    selected_query: Query<Entity, With<Selected>>,
+    mut camera_query: Query<&mut CameraController>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    let clicked = trigger.entity;
+    for selected in selected_query.iter().filter(|selected| *selected != clicked) {
+        commands.entity(selected).remove::<Selected>();
+    }
+    commands.entity(clicked).insert(Selected);
+
+    let Ok(mut controller) = camera_query.single_mut() else {
+        return;
+    };
+    let now = time.elapsed_secs_f64();
+    let double_click = controller
+        .last_click
+        .is_some_and(|(entity, at)| entity == clicked && now - at <= DOUBLE_CLICK_SECONDS);
+    if double_click {
+        controller.follow = Some(clicked);
+        controller.last_click = None;
+    } else {
+        controller.last_click = Some((clicked, now));
+    }
+}
@@ -0,0 +1,286 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use crate::Size;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColliderShape {
+    #[default]
+    Aabb,   // the full Size rectangle
+    Circle, // fits inside the Size, radius is half the smaller side
+}
+
+/// Takes part in overlap resolution. Pushable colliders get moved out of everything they overlap,
+/// solid ones (buildings, the big enemy) never move out of the way.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Collider {
+    pub shape: ColliderShape,
+    pub pushable: bool,
+}
+
+impl Collider {
+    pub fn solid() -> Self {
+        Self { shape: ColliderShape::Aabb, pushable: false }
+    }
+
+    pub fn pushable() -> Self {
+        Self { shape: ColliderShape::Aabb, pushable: true }
+    }
+
+    pub fn with_shape(mut self, shape: ColliderShape) -> Self {
+        self.shape = shape;
+        self
+    }
+}
+
+/// World space bounds of an entity, built from its Transform and Size
+#[derive(Debug, Clone, Copy)]
+pub enum Hitbox {
+    Aabb(Rect),
+    Circle { center: Vec2, radius: f32 },
+}
+
+impl Hitbox {
+    pub fn new(center: Vec2, size: &Size, shape: ColliderShape) -> Self {
+        match shape {
+            ColliderShape::Aabb => Hitbox::Aabb(Rect::from_center_size(center, size.0)),
+            ColliderShape::Circle => Hitbox::Circle { center, radius: size.0.min_element() / 2.0 },
+        }
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        match self {
+            Hitbox::Aabb(rect) => rect.contains(point),
+            Hitbox::Circle { center, radius } => center.distance(point) <= *radius,
+        }
+    }
+
+    // Distance between the edges of two hitboxes, negative while they overlap (by the overlap depth)
+    pub fn gap(&self, other: &Hitbox) -> f32 {
+        match (self, other) {
+            (Hitbox::Aabb(a), Hitbox::Aabb(b)) => {
+                let dx = (a.min.x - b.max.x).max(b.min.x - a.max.x);
+                let dy = (a.min.y - b.max.y).max(b.min.y - a.max.y);
+                if dx > 0.0 || dy > 0.0 {
+                    Vec2::new(dx.max(0.0), dy.max(0.0)).length()
+                } else {
+                    dx.max(dy)
+                }
+            },
+            (Hitbox::Circle { center: a, radius: ra }, Hitbox::Circle { center: b, radius: rb }) => {
+                a.distance(*b) - ra - rb
+            },
+            (Hitbox::Circle { center, radius }, Hitbox::Aabb(rect))
+            | (Hitbox::Aabb(rect), Hitbox::Circle { center, radius }) => {
+                if rect.contains(*center) {
+                    -(edge_depth(rect, *center).0 + radius)
+                } else {
+                    center.distance(center.clamp(rect.min, rect.max)) - radius
+                }
+            },
+        }
+    }
+
+    // "In range" means the edges are at most `range` apart, so big targets can be hit from further away
+    pub fn in_range(&self, other: &Hitbox, range: f32) -> bool {
+        self.gap(other) <= range
+    }
+
+    // Smallest offset that moves this hitbox out of `other`, None when they don't overlap
+    pub fn penetration(&self, other: &Hitbox) -> Option<Vec2> {
+        let depth = -self.gap(other);
+        if depth <= 0.0 {
+            return None;
+        }
+        let push = match (self, other) {
+            (Hitbox::Aabb(a), Hitbox::Aabb(b)) => {
+                let overlap = a.intersect(*b).size();
+                let away = a.center() - b.center();
+                if overlap.x < overlap.y {
+                    Vec2::new(overlap.x * sign_or_one(away.x), 0.0)
+                } else {
+                    Vec2::new(0.0, overlap.y * sign_or_one(away.y))
+                }
+            },
+            (Hitbox::Circle { center: a, .. }, Hitbox::Circle { center: b, .. }) => {
+                (*a - *b).normalize_or(Vec2::X) * depth
+            },
+            (Hitbox::Circle { center, .. }, Hitbox::Aabb(rect)) => circle_out_of_rect(*center, rect, depth),
+            (Hitbox::Aabb(rect), Hitbox::Circle { center, .. }) => -circle_out_of_rect(*center, rect, depth),
+        };
+        Some(push)
+    }
+}
+
+// Distance from a point inside the rect to its closest edge, and the outward direction of that edge
+fn edge_depth(rect: &Rect, point: Vec2) -> (f32, Vec2) {
+    [
+        (point.x - rect.min.x, Vec2::NEG_X),
+        (rect.max.x - point.x, Vec2::X),
+        (point.y - rect.min.y, Vec2::NEG_Y),
+        (rect.max.y - point.y, Vec2::Y),
+    ]
+    .into_iter()
+    .min_by(|a, b| a.0.total_cmp(&b.0))
+    .unwrap_or((0.0, Vec2::X))
+}
+
+fn circle_out_of_rect(center: Vec2, rect: &Rect, depth: f32) -> Vec2 {
+    if rect.contains(center) {
+        edge_depth(rect, center).1 * depth
+    } else {
+        (center - center.clamp(rect.min, rect.max)).normalize_or(Vec2::X) * depth
+    }
+}
+
+fn sign_or_one(value: f32) -> f32 {
+    if value < 0.0 { -1.0 } else { 1.0 }
+}
+
+/// Read-only spatial lookups over everything with a Size
+#[derive(SystemParam)]
+pub struct SpatialQuery<'w, 's> {
+    hitboxes: Query<'w, 's, (Entity, &'static Transform, &'static Size, Option<&'static Collider>)>,
+}
+
+impl SpatialQuery<'_, '_> {
+    pub fn hitbox(&self, entity: Entity) -> Option<Hitbox> {
+        self.hitboxes
+            .get(entity)
+            .ok()
+            .map(|(_, transform, size, maybe_collider)| to_hitbox(transform, size, maybe_collider))
+    }
+
+    // Whether the hitboxes of two entities are at most `range` apart, false if one of them has no Size
+    pub fn in_range(&self, a: Entity, b: Entity, range: f32) -> bool {
+        match (self.hitbox(a), self.hitbox(b)) {
+            (Some(a), Some(b)) => a.in_range(&b, range),
+            _ => false,
+        }
+    }
+
+    // Every entity whose hitbox contains the point
+    pub fn at_point(&self, point: Vec2) -> impl Iterator<Item = Entity> + '_ {
+        self.iter().filter(move |(_, hitbox)| hitbox.contains(point)).map(|(entity, _)| entity)
+    }
+
+    // Every entity whose edge is at most `range` away from the hitbox, paired with that gap
+    pub fn within(&self, hitbox: Hitbox, range: f32) -> impl Iterator<Item = (Entity, f32)> + '_ {
+        self.iter()
+            .map(move |(entity, other)| (entity, hitbox.gap(&other)))
+            .filter(move |(_, gap)| *gap <= range)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (Entity, Hitbox)> + '_ {
+        self.hitboxes
+            .iter()
+            .map(|(entity, transform, size, maybe_collider)| (entity, to_hitbox(transform, size, maybe_collider)))
+    }
+}
+
+fn to_hitbox(transform: &Transform, size: &Size, maybe_collider: Option<&Collider>) -> Hitbox {
+    let shape = maybe_collider.map_or(ColliderShape::Aabb, |collider| collider.shape);
+    Hitbox::new(transform.translation.xy(), size, shape)
+}
+
+// Pushes overlapping colliders apart: two pushable ones share the push, solid ones push the other one out fully
+pub fn collision_resolution_system(
+    mut query: Query<(Entity, &mut Transform, &Size, &Collider)>,
+) {
+    let colliders: Vec<(Entity, Hitbox, bool)> = query
+        .iter()
+        .map(|(entity, transform, size, collider)| (entity, to_hitbox(transform, size, Some(collider)), collider.pushable))
+        .collect();
+
+    let mut offsets = vec![Vec2::ZERO; colliders.len()];
+    for (i, (_, hitbox, pushable)) in colliders.iter().enumerate() {
+        for (j, (_, other, other_pushable)) in colliders.iter().enumerate().skip(i + 1) {
+            let Some(push) = hitbox.penetration(other) else {
+                continue;
+            };
+            match (pushable, other_pushable) {
+                (true, true) => {
+                    offsets[i] += push * 0.5;
+                    offsets[j] -= push * 0.5;
+                },
+                (true, false) => offsets[i] += push,
+                (false, true) => offsets[j] -= push,
+                (false, false) => {},
+            }
+        }
+    }
+
+    for ((entity, _, _), offset) in colliders.iter().zip(offsets) {
+        if offset == Vec2::ZERO {
+            continue;
+        }
+        if let Ok((_, mut transform, _, _)) = query.get_mut(*entity) {
+            transform.translation += offset.extend(0.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aabb(x: f32, y: f32, size: f32) -> Hitbox {
+        Hitbox::new(Vec2::new(x, y), &Size::square(size), ColliderShape::Aabb)
+    }
+
+    fn circle(x: f32, y: f32, size: f32) -> Hitbox {
+        Hitbox::new(Vec2::new(x, y), &Size::square(size), ColliderShape::Circle)
+    }
+
+    #[test]
+    fn aabb_gap_is_the_distance_between_edges() {
+        assert_eq!(aabb(0.0, 0.0, 10.0).gap(&aabb(30.0, 0.0, 10.0)), 20.0);
+        // Diagonal neighbours measure from corner to corner
+        assert_eq!(aabb(0.0, 0.0, 10.0).gap(&aabb(40.0, 50.0, 10.0)), Vec2::new(30.0, 40.0).length());
+    }
+
+    #[test]
+    fn aabb_overlap_is_a_negative_gap() {
+        assert_eq!(aabb(0.0, 0.0, 10.0).gap(&aabb(6.0, 0.0, 10.0)), -4.0);
+        assert!(aabb(0.0, 0.0, 10.0).penetration(&aabb(20.0, 0.0, 10.0)).is_none());
+    }
+
+    #[test]
+    fn aabb_penetration_pushes_out_along_the_shallow_axis() {
+        let push = aabb(0.0, 0.0, 10.0).penetration(&aabb(6.0, 1.0, 10.0)).unwrap();
+        assert_eq!(push, Vec2::new(-4.0, 0.0));
+    }
+
+    #[test]
+    fn circle_gap_and_penetration() {
+        assert_eq!(circle(0.0, 0.0, 10.0).gap(&circle(25.0, 0.0, 10.0)), 15.0);
+        let push = circle(0.0, 0.0, 10.0).penetration(&circle(0.0, 8.0, 10.0)).unwrap();
+        assert_eq!(push, Vec2::new(0.0, -2.0));
+    }
+
+    #[test]
+    fn circle_against_aabb() {
+        // A circle outside a corner is further away than the plain axis distance
+        let gap = circle(20.0, 20.0, 10.0).gap(&aabb(0.0, 0.0, 20.0));
+        assert!((gap - (Vec2::splat(10.0).length() - 5.0)).abs() < 1e-4);
+
+        // A circle with its centre inside the box is pushed out of the closest edge
+        let push = circle(8.0, 0.0, 10.0).penetration(&aabb(0.0, 0.0, 20.0)).unwrap();
+        assert_eq!(push, Vec2::new(7.0, 0.0));
+        assert_eq!(aabb(0.0, 0.0, 20.0).penetration(&circle(8.0, 0.0, 10.0)).unwrap(), -push);
+    }
+
+    #[test]
+    fn range_is_measured_between_edges() {
+        let attacker = aabb(0.0, 0.0, 10.0);
+        let big_target = aabb(100.0, 0.0, 100.0);
+        assert!(attacker.in_range(&big_target, 45.0));
+        assert!(!attacker.in_range(&big_target, 44.0));
+        assert!(attacker.in_range(&aabb(5.0, 0.0, 10.0), 0.0));
+    }
+
+    #[test]
+    fn contains_follows_the_shape() {
+        let corner = Vec2::new(4.5, 4.5);
+        assert!(aabb(0.0, 0.0, 10.0).contains(corner));
+        assert!(!circle(0.0, 0.0, 10.0).contains(corner));
+    }
+}
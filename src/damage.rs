@@ -0,0 +1,238 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+use crate::floating_text::{FloatingTextCommands, FloatingTextStyle, TextPopup};
+use crate::status_effects::StatusEffect;
+
+const DEFAULT_CRIT_CHANCE: f32 = 0.0; // only attacks built for it crit, see `with_crit`
+const DEFAULT_CRIT_MULTIPLIER: f32 = 2.0;
+pub const MAX_ARMOUR: f32 = 0.8; // armour never blocks more than this, every hit gets some damage through
+const RESISTED_THRESHOLD: f32 = 0.5; // resistances from here on show up as "RESISTED"
+const CRIT_COLOR: Color = Color::srgb(1.0, 0.9, 0.1);
+const RESISTED_COLOR: Color = Color::srgb(0.6, 0.6, 0.6);
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DamageType {
+    #[default]
+    Kinetic,
+    Fire,
+    Electric,
+}
+
+impl DamageType {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DamageType::Kinetic => "kinetic",
+            DamageType::Fire => "fire",
+            DamageType::Electric => "electric",
+        }
+    }
+
+    // Colour of the floating damage numbers
+    pub fn color(&self) -> Color {
+        match self {
+            DamageType::Kinetic => Color::srgb(1.0, 0.2, 0.2),
+            DamageType::Fire => Color::srgb(1.0, 0.5, 0.1),
+            DamageType::Electric => Color::srgb(0.3, 0.8, 1.0),
+        }
+    }
+}
+
+/// A single hit before the target's armour and resistances are applied
+#[derive(Debug, Clone, Copy)]
+pub struct Damage {
+    pub amount: f32,
+    pub kind: DamageType,
+    pub crit_chance: f32,
+    pub crit_multiplier: f32,
+    pub status: Option<StatusEffect>, // put on the target when the hit lands
+}
+
+impl Damage {
+    pub fn new(amount: f32, kind: DamageType) -> Self {
+        Self {
+            amount,
+            kind,
+            crit_chance: DEFAULT_CRIT_CHANCE,
+            crit_multiplier: DEFAULT_CRIT_MULTIPLIER,
+            status: None,
+        }
+    }
+
+    pub fn with_status(mut self, status: StatusEffect) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    pub fn with_crit(mut self, chance: f32, multiplier: f32) -> Self {
+        self.crit_chance = chance;
+        self.crit_multiplier = multiplier;
+        self
+    }
+
+    // Rolls for a crit and applies the defences, returns the final amount and whether it crit
+    pub fn roll(&self, armour: Option<&Armour>, resistances: Option<&Resistances>) -> (f32, bool) {
+        let crit = rand::thread_rng().gen_bool(self.crit_chance.clamp(0.0, 1.0) as f64);
+        let multiplier = if crit { self.crit_multiplier } else { 1.0 };
+        let blocked = armour.map_or(0.0, |armour| armour.0.clamp(0.0, MAX_ARMOUR));
+        let resisted = resistances.map_or(0.0, |resistances| resistances.get(self.kind));
+        let amount = self.amount * multiplier * (1.0 - blocked) * (1.0 - resisted);
+        (amount.max(0.0), crit)
+    }
+}
+
+// Fraction of incoming damage of every type that is blocked, 0.2 = 20% less damage. Capped at MAX_ARMOUR
+#[derive(Component)]
+pub struct Armour(pub f32);
+
+/// Per damage type fraction that is ignored, on top of Armour. 1.0 = immune, negative values are weaknesses.
+#[derive(Component, Deserialize, Debug, Clone, Copy, Default)]
+#[serde(default)]
+pub struct Resistances {
+    pub kinetic: f32,
+    pub fire: f32,
+    pub electric: f32,
+}
+
+impl Resistances {
+    pub fn get(&self, kind: DamageType) -> f32 {
+        match kind {
+            DamageType::Kinetic => self.kinetic,
+            DamageType::Fire => self.fire,
+            DamageType::Electric => self.electric,
+        }
+    }
+}
+
+/// Sent on the target after a hit landed, with the damage it actually took
+#[derive(EntityEvent)]
+pub struct DamageEvent {
+    pub entity: Entity,
+    pub source: Entity,
+    pub kind: DamageType,
+    pub amount: f32,
+    pub absorbed: f32, // soaked up by a shield
+    pub crit: bool,
+    pub resisted: bool,
+}
+
+impl DamageEvent {
+    // Damage number shown over the target, hits of the same kind in quick succession add up
+    pub fn popup(&self, color: Color, position: Vec3) -> TextPopup {
+        if self.amount <= 0.0 && self.absorbed > 0.0 {
+            return TextPopup::new("BLOCKED", color, position).with_style(FloatingTextStyle::DAMAGE).over(self.entity);
+        }
+        let prefix = match (self.crit, self.resisted) {
+            (true, _) => "CRIT ",
+            (false, true) => "RESISTED ",
+            (false, false) => "",
+        };
+        TextPopup::amount(prefix, self.amount, color, position).over(self.entity)
+    }
+}
+
+pub fn is_resisted(kind: DamageType, resistances: Option<&Resistances>) -> bool {
+    resistances.is_some_and(|resistances| resistances.get(kind) >= RESISTED_THRESHOLD)
+}
+
+// Shows the damage number above whatever was hit
+pub fn on_damage(
+    trigger: On<DamageEvent>,
+    transform_query: Query<&Transform>,
+    name_query: Query<&Name>,
+    mut commands: Commands,
+) {
+    let Ok(transform) = transform_query.get(trigger.entity) else {
+        return;
+    };
+    let color = match (trigger.crit, trigger.resisted) {
+        (true, _) => CRIT_COLOR,
+        (false, true) => RESISTED_COLOR,
+        (false, false) => trigger.kind.color(),
+    };
+    commands.floating_text(trigger.popup(color, transform.translation));
+
+    let name = |entity: Entity| name_query.get(entity).map_or("Something".to_string(), |name| name.to_string());
+    debug!(
+        "{} hit {} for {} {} damage{}{}",
+        name(trigger.source),
+        name(trigger.entity),
+        trigger.amount as i32,
+        trigger.kind.label(),
+        if trigger.crit { " (CRIT)" } else { "" },
+        if trigger.resisted { " (RESISTED)" } else { "" },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resistances(kinetic: f32, fire: f32, electric: f32) -> Resistances {
+        Resistances { kinetic, fire, electric }
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-4, "expected {expected}, got {actual}");
+    }
+
+    #[test]
+    fn armour_blocks_a_fraction_of_every_hit() {
+        let (amount, _) = Damage::new(100.0, DamageType::Fire).roll(Some(&Armour(0.25)), None);
+        assert_close(amount, 75.0);
+    }
+
+    #[test]
+    fn armour_is_capped_so_some_damage_gets_through() {
+        let (amount, _) = Damage::new(100.0, DamageType::Kinetic).roll(Some(&Armour(1.5)), None);
+        assert_close(amount, 100.0 * (1.0 - MAX_ARMOUR));
+        // Negative armour does not add damage
+        let (amount, _) = Damage::new(100.0, DamageType::Kinetic).roll(Some(&Armour(-0.5)), None);
+        assert_close(amount, 100.0);
+    }
+
+    #[test]
+    fn resistances_scale_only_their_own_type() {
+        let resist = resistances(0.5, 1.0, -0.25);
+        let roll = |kind| Damage::new(40.0, kind).roll(None, Some(&resist)).0;
+        assert_close(roll(DamageType::Kinetic), 20.0);
+        assert_close(roll(DamageType::Fire), 0.0);
+        // A weakness makes the hit stronger
+        assert_close(roll(DamageType::Electric), 50.0);
+    }
+
+    #[test]
+    fn armour_and_resistances_stack() {
+        let (amount, _) = Damage::new(100.0, DamageType::Kinetic).roll(Some(&Armour(0.5)), Some(&resistances(0.5, 0.0, 0.0)));
+        assert_close(amount, 25.0);
+    }
+
+    #[test]
+    fn resisted_from_the_threshold_on() {
+        let resist = resistances(RESISTED_THRESHOLD, RESISTED_THRESHOLD - 0.01, 1.0);
+        assert!(is_resisted(DamageType::Kinetic, Some(&resist)));
+        assert!(!is_resisted(DamageType::Fire, Some(&resist)));
+        assert!(is_resisted(DamageType::Electric, Some(&resist)));
+        assert!(!is_resisted(DamageType::Kinetic, None));
+    }
+
+    #[test]
+    fn certain_crit_multiplies_the_hit() {
+        for _ in 0..20 {
+            let (amount, crit) = Damage::new(10.0, DamageType::Kinetic).with_crit(1.0, 3.0).roll(None, None);
+            assert!(crit);
+            assert_close(amount, 30.0);
+        }
+    }
+
+    #[test]
+    fn no_crit_chance_never_crits() {
+        for _ in 0..20 {
+            let (amount, crit) = Damage::new(10.0, DamageType::Kinetic).with_crit(0.0, 3.0).roll(None, None);
+            assert!(!crit);
+            assert_close(amount, 10.0);
+        }
+        // The default is no crits at all
+        assert!(!Damage::new(10.0, DamageType::Kinetic).roll(None, None).1);
+    }
+}
@@ -0,0 +1,167 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// Baseline values the presets scale from (these were the old hard-coded numbers).
+// Enemy stats come from assets/data/enemies.ron and are scaled by the multipliers below.
+const BASE_HOME_BASE_HEALTH: f32 = 500.0;
+const BASE_STARTING_PARTS: u32 = 10;
+const BASE_SCOUTING_YIELD: u32 = 3;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DifficultyPreset {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Custom,
+}
+
+impl DifficultyPreset {
+    // The presets offered before a run, Custom needs its values from the command line
+    pub const PICKABLE: [DifficultyPreset; 3] = [DifficultyPreset::Easy, DifficultyPreset::Normal, DifficultyPreset::Hard];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DifficultyPreset::Easy => "Easy",
+            DifficultyPreset::Normal => "Normal",
+            DifficultyPreset::Hard => "Hard",
+            DifficultyPreset::Custom => "Custom",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            DifficultyPreset::Easy => "Weaker enemies, a sturdier base and more parts",
+            DifficultyPreset::Normal => "The game as it was tuned",
+            DifficultyPreset::Hard => "Fast, hard hitting enemies and scarce parts",
+            DifficultyPreset::Custom => "Values from the command line",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "easy" => Some(DifficultyPreset::Easy),
+            "normal" => Some(DifficultyPreset::Normal),
+            "hard" => Some(DifficultyPreset::Hard),
+            "custom" => Some(DifficultyPreset::Custom),
+            _ => None,
+        }
+    }
+}
+
+/// Multipliers and starting values for the selected difficulty, chosen once at run start
+#[derive(Resource, Debug, Clone)]
+pub struct Difficulty {
+    pub preset: DifficultyPreset,
+    pub enemy_health: f32,   // multiplier on enemy Health
+    pub enemy_damage: f32,   // multiplier on enemy Attack::damage
+    pub enemy_cooldown: f32, // multiplier on enemy Attack::max_cooldown (higher = slower attacks)
+    pub enemy_speed: f32,    // multiplier on enemy Movement::speed
+    pub base_health: f32,    // multiplier on HomeBase Health
+    pub starting_parts: u32,
+    pub scouting_yield: u32, // parts added to the inventory per scouting trip
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Self::from_preset(DifficultyPreset::Normal)
+    }
+}
+
+impl Difficulty {
+    pub fn from_preset(preset: DifficultyPreset) -> Self {
+        match preset {
+            DifficultyPreset::Easy => Self {
+                preset,
+                enemy_health: 0.75,
+                enemy_damage: 0.6,
+                enemy_cooldown: 1.25,
+                enemy_speed: 0.8,
+                base_health: 1.5,
+                starting_parts: 16,
+                scouting_yield: 4,
+            },
+            // Normal and Custom use the enemies.ron stats and the base values as they are
+            DifficultyPreset::Normal | DifficultyPreset::Custom => Self {
+                preset,
+                enemy_health: 1.0,
+                enemy_damage: 1.0,
+                enemy_cooldown: 1.0,
+                enemy_speed: 1.0,
+                base_health: 1.0,
+                starting_parts: BASE_STARTING_PARTS,
+                scouting_yield: BASE_SCOUTING_YIELD,
+            },
+            DifficultyPreset::Hard => Self {
+                preset,
+                enemy_health: 1.5,
+                enemy_damage: 1.5,
+                enemy_cooldown: 0.75,
+                enemy_speed: 1.5,
+                base_health: 0.8,
+                starting_parts: 6,
+                scouting_yield: 2,
+            },
+        }
+    }
+
+    /// Reads the difficulty from command line arguments, e.g. `--difficulty=hard`.
+    /// A custom run takes its values from extra flags like `--enemy-health=1.2` or `--starting-parts=8`,
+    /// unspecified values keep the Normal tuning. None without a `--difficulty` flag, the player picks one in game then.
+    /// Flags that can't be parsed end up in `warnings`.
+    pub fn from_args(args: impl Iterator<Item = String>, warnings: &mut Vec<String>) -> Option<Self> {
+        let args: Vec<String> = args.collect();
+
+        let name = args.iter().find_map(|arg| arg.strip_prefix("--difficulty="))?;
+        let preset = DifficultyPreset::parse(name).unwrap_or_else(|| {
+            warnings.push(format!("Unknown difficulty '{}', falling back to Normal", name));
+            DifficultyPreset::Normal
+        });
+
+        let mut difficulty = Self::from_preset(preset);
+        if preset != DifficultyPreset::Custom {
+            return Some(difficulty);
+        }
+
+        for arg in &args {
+            let Some((flag, value)) = arg.split_once('=') else {
+                continue;
+            };
+            let parsed = match flag {
+                "--enemy-health" => value.parse().map(|v| difficulty.enemy_health = v).is_ok(),
+                "--enemy-damage" => value.parse().map(|v| difficulty.enemy_damage = v).is_ok(),
+                "--enemy-cooldown" => value.parse().map(|v| difficulty.enemy_cooldown = v).is_ok(),
+                "--enemy-speed" => value.parse().map(|v| difficulty.enemy_speed = v).is_ok(),
+                "--base-health" => value.parse().map(|v| difficulty.base_health = v).is_ok(),
+                "--starting-parts" => value.parse().map(|v| difficulty.starting_parts = v).is_ok(),
+                "--scouting-yield" => value.parse().map(|v| difficulty.scouting_yield = v).is_ok(),
+                _ => true,
+            };
+            if !parsed {
+                warnings.push(format!("Could not parse '{}', keeping the default value", arg));
+            }
+        }
+
+        Some(difficulty)
+    }
+
+    pub fn enemy_max_health(&self, base: f32) -> f32 {
+        base * self.enemy_health
+    }
+
+    pub fn enemy_attack_damage(&self, base: f32) -> f32 {
+        base * self.enemy_damage
+    }
+
+    pub fn enemy_attack_cooldown(&self, base: f32) -> f32 {
+        base * self.enemy_cooldown
+    }
+
+    pub fn enemy_move_speed(&self, base: f32) -> f32 {
+        base * self.enemy_speed
+    }
+
+    pub fn home_base_max_health(&self) -> f32 {
+        BASE_HOME_BASE_HEALTH * self.base_health
+    }
+}
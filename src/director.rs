@@ -0,0 +1,247 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use rand::Rng;
+use crate::bob_state::{Dead, Idling, Returning, Scouting};
+use crate::damage::DamageEvent;
+use crate::enemies::{Boss, EnemyBehaviour, EnemyFocus, EnemySpawner};
+use crate::map::BattleMap;
+use crate::message_log::GameLogCommands;
+use crate::{Bob, ComponentsInventory, Enemy, Health, HomeBase};
+
+const FIRST_WAVE_DELAY: f32 = 30.0; // time to get the first Bobs built before anything shows up
+const BUILD_UP_SECONDS: f32 = 40.0;
+const PEAK_SECONDS: f32 = 20.0;
+const RELAX_SECONDS: f32 = 25.0; // halved for a strong player, one and a half times for a weak one
+const INTENSITY_DECAY: f32 = 0.02; // per second
+const PEAK_INTENSITY: f32 = 0.8; // the enemy backs off once the player is under this much pressure
+const CALM_INTENSITY: f32 = 0.3; // and only comes back once things calmed down again
+const LOW_BASE_HEALTH: f32 = 0.3; // below this fraction the director stops going for the base
+const SPAWN_JITTER: f32 = 40.0; // so enemies from the same spawn point do not stack up
+
+// The Bobs, base and inventory the director sizes the player up from
+#[derive(SystemParam)]
+pub struct PlayerWatch<'w, 's> {
+    bob_query: Query<'w, 's, (Has<Scouting>, Has<Returning>, Has<Idling>), (With<Bob>, Without<Dead>)>,
+    home_base_query: Query<'w, 's, &'static Health, With<HomeBase>>,
+    inventory_query: Query<'w, 's, &'static ComponentsInventory>,
+}
+
+impl PlayerWatch<'_, '_> {
+    fn strength(&self) -> PlayerStrength {
+        let mut strength = PlayerStrength {
+            base_health: self.home_base_query.iter().next().map_or(0.0, |health| health.current / health.max.max(1.0)),
+            parts: self.inventory_query.iter().next().map_or(0, |inventory| inventory.count()),
+            ..default()
+        };
+        for (scouting, returning, idling) in self.bob_query.iter() {
+            strength.bobs += 1;
+            if scouting || returning {
+                strength.scouts += 1;
+            } else if idling {
+                strength.idle += 1;
+            }
+        }
+        strength
+    }
+}
+
+/// The phases of the pressure cycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DirectorMood {
+    Relax,   // no spawns, time to rebuild
+    BuildUp, // a steady trickle of enemies
+    Peak,    // a wave, spawning fast
+}
+
+impl DirectorMood {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DirectorMood::Relax => "Relax",
+            DirectorMood::BuildUp => "Build up",
+            DirectorMood::Peak => "Peak",
+        }
+    }
+}
+
+// What the director knows about the player
+#[derive(Debug, Default)]
+struct PlayerStrength {
+    bobs: usize,
+    scouts: usize,
+    idle: usize,
+    base_health: f32, // fraction of max health
+    parts: u32,
+}
+
+impl PlayerStrength {
+    // Roughly 0.0 for a player barely holding on to 1.0 for one with a full army, base and inventory
+    fn score(&self) -> f32 {
+        (self.bobs as f32 / 8.0).min(1.0) * 0.5 + (self.parts as f32 / 20.0).min(1.0) * 0.2 + self.base_health * 0.3
+    }
+
+    // Goes where the Bobs are, but leaves a badly damaged base alone so the player can recover
+    fn focus(&self) -> EnemyFocus {
+        if self.base_health < LOW_BASE_HEALTH {
+            if self.scouts > 0 { EnemyFocus::Scouts } else { EnemyFocus::IdleGrid }
+        } else if self.scouts > 0 && self.scouts >= self.idle {
+            EnemyFocus::Scouts
+        } else if self.idle > 3 {
+            EnemyFocus::IdleGrid // punish a big idle army sitting around
+        } else {
+            EnemyFocus::Base
+        }
+    }
+
+    // Spawn weights per enemy id, the player's choices decide what shows up
+    fn spawn_weights(&self) -> [(&'static str, f32); 4] {
+        [
+            ("raider", 2.0 + self.scouts as f32),
+            ("tank", if self.bobs >= 3 { 1.0 + self.score() * 2.0 } else { 0.0 }),
+            ("sniper", 1.0 + self.idle as f32 * 0.5),
+            ("thief", if self.parts >= 4 { self.parts as f32 / 5.0 } else { 0.0 }),
+        ]
+    }
+}
+
+/// Paces the match: watches the player's strength and decides when enemies come, which ones and what they go after
+#[derive(Resource)]
+pub struct Director {
+    pub mood: DirectorMood,
+    pub intensity: f32, // 0.0 to 1.0, raised by damage to the base and the Bobs
+    pub focus: EnemyFocus,
+    pub alive_cap: usize, // enemies besides the boss that may be alive at once, boss summons count towards it too
+    mood_timer: Timer,
+    spawn_timer: Timer,
+}
+
+impl Default for Director {
+    fn default() -> Self {
+        Self {
+            mood: DirectorMood::Relax,
+            intensity: 0.0,
+            focus: EnemyFocus::Base,
+            alive_cap: 0,
+            mood_timer: Timer::from_seconds(FIRST_WAVE_DELAY, TimerMode::Once),
+            spawn_timer: Timer::from_seconds(1.0, TimerMode::Once),
+        }
+    }
+}
+
+impl Director {
+    fn set_mood(&mut self, mood: DirectorMood, score: f32) {
+        let seconds = match mood {
+            DirectorMood::Relax => RELAX_SECONDS * (1.5 - score.clamp(0.0, 1.0)),
+            DirectorMood::BuildUp => BUILD_UP_SECONDS,
+            DirectorMood::Peak => PEAK_SECONDS,
+        };
+        self.mood = mood;
+        self.mood_timer = Timer::from_seconds(seconds, TimerMode::Once);
+        self.spawn_timer = Timer::from_seconds(self.spawn_interval(score), TimerMode::Once);
+    }
+
+    // Stronger players get enemies more often
+    fn spawn_interval(&self, score: f32) -> f32 {
+        let score = score.clamp(0.0, 1.0);
+        match self.mood {
+            DirectorMood::Relax | DirectorMood::BuildUp => 14.0 - 7.0 * score,
+            DirectorMood::Peak => 6.0 - 3.0 * score,
+        }
+    }
+
+    fn max_alive(&self, score: f32) -> usize {
+        let cap = 2 + (score.clamp(0.0, 1.0) * 6.0).round() as usize;
+        if self.mood == DirectorMood::Peak { cap + 2 } else { cap }
+    }
+}
+
+// Damage to the player's side raises the intensity relative to how much it hurt
+pub fn on_director_damage(
+    trigger: On<DamageEvent>,
+    target_query: Query<&Health, Or<(With<Bob>, With<HomeBase>)>>,
+    mut director: ResMut<Director>,
+) {
+    let Ok(health) = target_query.get(trigger.entity) else {
+        return;
+    };
+    director.intensity = (director.intensity + trigger.amount / health.max.max(1.0)).min(1.0);
+}
+
+pub fn director_system(
+    mut director: ResMut<Director>,
+    player: PlayerWatch,
+    mut enemy_query: Query<(&EnemyBehaviour, &mut EnemyFocus), (With<Enemy>, Without<Boss>)>,
+    spawner: EnemySpawner,
+    map: Res<BattleMap>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    let strength = player.strength();
+    let score = strength.score();
+    director.alive_cap = director.max_alive(score);
+
+    director.intensity = (director.intensity - INTENSITY_DECAY * time.delta_secs()).max(0.0);
+    director.mood_timer.tick(time.delta());
+
+    // Pressure cycle: relax until things calmed down, build up, peak, and back off early if the player is struggling
+    let next_mood = match director.mood {
+        DirectorMood::Relax if director.mood_timer.is_finished() && director.intensity < CALM_INTENSITY => Some(DirectorMood::BuildUp),
+        DirectorMood::BuildUp | DirectorMood::Peak if director.intensity > PEAK_INTENSITY => Some(DirectorMood::Relax),
+        DirectorMood::BuildUp if director.mood_timer.is_finished() => Some(DirectorMood::Peak),
+        DirectorMood::Peak if director.mood_timer.is_finished() => Some(DirectorMood::Relax),
+        _ => None,
+    };
+    if let Some(mood) = next_mood {
+        director.set_mood(mood, score);
+        match mood {
+            DirectorMood::Relax => commands.log_info("The enemy pulls back to regroup"),
+            DirectorMood::BuildUp => commands.log_info("Enemies spotted on the horizon"),
+            DirectorMood::Peak => commands.log_warning("A wave is coming!"),
+        }
+        debug!("Director: {} (intensity {:.2}, strength {:.2})", mood.label(), director.intensity, score);
+    }
+
+    let focus = strength.focus();
+    if focus != director.focus {
+        director.focus = focus;
+        if director.mood != DirectorMood::Relax {
+            commands.log_warning(format!("The enemy turns on {}!", focus.label()));
+        }
+    }
+    for (behaviour, mut enemy_focus) in enemy_query.iter_mut() {
+        if !behaviour.follows_director() {
+            continue;
+        }
+        // Raiders never give up on hunting Bobs
+        let focus = if focus == EnemyFocus::Base { behaviour.default_focus() } else { focus };
+        if *enemy_focus != focus {
+            *enemy_focus = focus;
+        }
+    }
+
+    // Nothing spawns while relaxing
+    if director.mood == DirectorMood::Relax || !director.spawn_timer.tick(time.delta()).is_finished() {
+        return;
+    }
+    let interval = director.spawn_interval(score);
+    director.spawn_timer = Timer::from_seconds(interval, TimerMode::Once);
+    if enemy_query.iter().count() >= director.alive_cap {
+        return;
+    }
+
+    let weights = strength.spawn_weights();
+    let total: f32 = weights.iter().map(|(_, weight)| weight).sum();
+    let mut rng = rand::thread_rng();
+    let mut roll = rng.gen_range(0.0..total);
+    let id = weights
+        .iter()
+        .find(|(_, weight)| {
+            roll -= weight;
+            roll < 0.0
+        })
+        .map_or(weights[0].0, |(id, _)| id);
+    let Some(def) = spawner.get(id) else {
+        commands.log_error(format!("Unknown enemy '{}'", id));
+        return;
+    };
+    let position = map.random_spawn() + Vec2::new(rng.gen_range(-SPAWN_JITTER..SPAWN_JITTER), rng.gen_range(-SPAWN_JITTER..SPAWN_JITTER));
+    spawner.spawn(&mut commands, def, position);
+}
@@ -0,0 +1,480 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::Deserialize;
+use crate::animation::Animator;
+use crate::bob_state::{Idling, Returning, Scouting};
+use crate::boss::{BossPhaseDef, BossPhases};
+use crate::collision::{Collider, SpatialQuery};
+use crate::damage::{Armour, Damage, DamageType, Resistances};
+use crate::director::{Director, DirectorMood};
+use crate::floating_text::{FloatingTextCommands, TextPopup};
+use crate::loot::LootType;
+use crate::map::BattleMap;
+use crate::message_log::GameLogCommands;
+use crate::status_effects::{ApplyStatusEvent, StatusEffect};
+use crate::{
+    move_towards, ArrivedAtTarget, AtTarget, Attack, Bob, ComponentsInventory, Difficulty, Enemy,
+    Health, HomeBase, Movement, Size, MELEE_RANGE,
+};
+
+const ENEMIES_RON: &str = include_str!("../assets/data/enemies.ron");
+const ENEMY_ARRIVAL_RADIUS: f32 = 5.0;
+const SUMMON_OFFSET: f32 = 80.0; // gap between the boss and freshly summoned minions
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct EnemyAttackDef {
+    pub damage: f32,
+    pub damage_type: DamageType,
+    pub cooldown: f32,
+    #[serde(default = "default_attack_range")]
+    pub range: f32,
+    #[serde(default)]
+    pub projectile_speed: Option<f32>,
+    #[serde(default)]
+    pub burn: Option<f32>, // fire damage per second put on the target for a few seconds
+}
+
+fn default_attack_range() -> f32 {
+    MELEE_RANGE
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct StompDef {
+    pub interval: f32,
+    pub radius: f32,
+    pub stun: f32,
+}
+
+/// Which AI routine `enemy_system` runs for an enemy
+#[derive(Component, Deserialize, Debug, Clone)]
+pub enum EnemyBehaviour {
+    Siege,  // walk up to the base and hit it
+    Raider, // hunts Bobs that are out in the field, sieges when there are none
+    Sniper, // keeps its distance and shoots the base
+    Boss { minions: Vec<String>, interval: f32 },
+    Thief { steal: u32 }, // grabs parts from the inventory and runs off with them
+}
+
+impl EnemyBehaviour {
+    // What the enemy goes after until the director says otherwise
+    pub fn default_focus(&self) -> EnemyFocus {
+        match self {
+            EnemyBehaviour::Raider => EnemyFocus::Scouts,
+            _ => EnemyFocus::Base,
+        }
+    }
+
+    // Bosses and thieves follow their own plan, the rest take orders from the director
+    pub fn follows_director(&self) -> bool {
+        matches!(self, EnemyBehaviour::Siege | EnemyBehaviour::Raider | EnemyBehaviour::Sniper)
+    }
+}
+
+/// What an enemy attacks, the director changes it to keep up the pressure where it hurts
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnemyFocus {
+    Base,     // the home base
+    Scouts,   // Bobs out in the field, the base when there are none
+    IdleGrid, // Bobs waiting in the formation, the base when there are none
+}
+
+impl EnemyFocus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            EnemyFocus::Base => "the home base",
+            EnemyFocus::Scouts => "the scouts",
+            EnemyFocus::IdleGrid => "the idle Bobs",
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct EnemyDef {
+    pub id: String,
+    pub name: String,
+    pub sprite: String,
+    #[serde(default)]
+    pub tint: Option<(f32, f32, f32)>,
+    pub size: (f32, f32),
+    pub health: f32,
+    pub speed: f32,
+    #[serde(default)]
+    pub attack: Option<EnemyAttackDef>,
+    #[serde(default)]
+    pub armour: f32,
+    #[serde(default)]
+    pub resistances: Resistances,
+    #[serde(default)]
+    pub stomp: Option<StompDef>,
+    #[serde(default)]
+    pub phases: Vec<BossPhaseDef>, // only used by bosses
+    pub behaviour: EnemyBehaviour,
+}
+
+/// Every enemy type from `assets/data/enemies.ron`
+#[derive(Resource)]
+pub struct EnemyRoster {
+    enemies: Vec<EnemyDef>,
+}
+
+impl Default for EnemyRoster {
+    fn default() -> Self {
+        let enemies = ron::from_str(ENEMIES_RON).unwrap_or_else(|error| {
+            error!("Could not read enemies.ron: {}", error);
+            Vec::new()
+        });
+        Self { enemies }
+    }
+}
+
+impl EnemyRoster {
+    pub fn get(&self, id: &str) -> Option<&EnemyDef> {
+        self.enemies.iter().find(|enemy| enemy.id == id)
+    }
+}
+
+// Killing the boss wins the run
+#[derive(Component)]
+pub struct Boss;
+
+// Movement speed and attack of an enemy, already scaled by the difficulty
+#[derive(Component)]
+pub struct EnemyStats {
+    pub speed: f32,
+    pub attack: Option<EnemyAttackDef>,
+}
+
+impl EnemyStats {
+    fn attack(&self, target: Entity) -> Option<Attack> {
+        self.attack.as_ref().map(|def| {
+            let mut damage = Damage::new(def.damage, def.damage_type);
+            if let Some(burn) = def.burn {
+                damage = damage.with_status(StatusEffect::burn(burn, 4.0));
+            }
+            match def.projectile_speed {
+                Some(speed) => Attack::ranged(target, damage, def.cooldown, def.range, speed),
+                None => Attack { range: def.range, ..Attack::melee(target, damage, def.cooldown) },
+            }
+        })
+    }
+
+    fn range(&self) -> f32 {
+        self.attack.as_ref().map_or(MELEE_RANGE, |attack| attack.range)
+    }
+}
+
+// Who the enemy is currently going after
+#[derive(Component)]
+pub struct EnemyTarget(pub Entity);
+
+// Parts a thief got away with, returned to the inventory if it is killed before it escapes
+#[derive(Component)]
+pub struct Stolen(pub Vec<LootType>);
+
+// Enemy ability: every few seconds it stuns the Bobs around it and hardens its shell
+#[derive(Component)]
+pub struct Stomp {
+    timer: Timer,
+    radius: f32, // gap between the hitboxes
+    stun_duration: f32,
+}
+
+// Boss ability: keeps a few minions around, spawned in the order of the roster entry.
+// Follows the director, so it rests while the director relaxes and shares its cap on living enemies
+#[derive(Component)]
+pub struct Summoner {
+    timer: Timer,
+    minions: Vec<String>,
+    next: usize,
+}
+
+/// Looks enemy types up in the roster and spawns them scaled by the difficulty
+#[derive(SystemParam)]
+pub struct EnemySpawner<'w> {
+    roster: Res<'w, EnemyRoster>,
+    difficulty: Res<'w, Difficulty>,
+    asset_server: Res<'w, AssetServer>,
+}
+
+impl EnemySpawner<'_> {
+    pub fn get(&self, id: &str) -> Option<&EnemyDef> {
+        self.roster.get(id)
+    }
+
+    pub fn spawn(&self, commands: &mut Commands, def: &EnemyDef, position: Vec2) -> Entity {
+        spawn_enemy(commands, def, position, &self.difficulty, &self.asset_server)
+    }
+}
+
+pub fn spawn_enemy(
+    commands: &mut Commands,
+    def: &EnemyDef,
+    position: Vec2,
+    difficulty: &Difficulty,
+    asset_server: &AssetServer,
+) -> Entity {
+    let size = Size::new(def.size.0, def.size.1);
+    let size_vec = size.0;
+    let attack = def.attack.clone().map(|attack| EnemyAttackDef {
+        damage: difficulty.enemy_attack_damage(attack.damage),
+        cooldown: difficulty.enemy_attack_cooldown(attack.cooldown),
+        ..attack
+    });
+
+    let mut enemy = commands.spawn((
+        Enemy,
+        size,
+        Sprite {
+            image: asset_server.load(def.sprite.clone()),
+            custom_size: Some(size_vec),
+            color: def.tint.map_or(Color::WHITE, |(r, g, b)| Color::srgb(r, g, b)),
+            ..default()
+        },
+        Transform::from_xyz(position.x, position.y, 1.0),
+        Health::new(difficulty.enemy_max_health(def.health)),
+        Collider::solid(), // Bobs get shoved aside, enemies do not
+        (Armour(def.armour), def.resistances),
+        EnemyStats { speed: difficulty.enemy_move_speed(def.speed), attack },
+        def.behaviour.default_focus(),
+        def.behaviour.clone(),
+        Animator::new(def.sprite.clone()),
+        Name::new(def.name.clone()),
+    ));
+
+    if let Some(stomp) = &def.stomp {
+        enemy.insert(Stomp {
+            timer: Timer::from_seconds(stomp.interval, TimerMode::Repeating),
+            radius: stomp.radius,
+            stun_duration: stomp.stun,
+        });
+    }
+    if let EnemyBehaviour::Boss { minions, interval } = &def.behaviour {
+        enemy.insert((
+            Boss,
+            Summoner {
+                timer: Timer::from_seconds(*interval, TimerMode::Repeating),
+                minions: minions.clone(),
+                next: 0,
+            },
+            BossPhases::new(def.phases.clone()),
+        ));
+    }
+    enemy.id()
+}
+
+// Runs the AI routine of every enemy: pick a target, then walk to the spot it attacks that target from
+pub fn enemy_system(
+    mut enemy_query: Query<(
+        Entity,
+        &EnemyBehaviour,
+        &EnemyFocus,
+        &EnemyStats,
+        &Transform,
+        Option<&Stolen>,
+        Option<&EnemyTarget>,
+        Option<&Attack>,
+        Option<&mut Movement>,
+        Option<&AtTarget>,
+    ), With<Enemy>>,
+    home_base_query: Query<(Entity, &Transform, &Size), With<HomeBase>>,
+    field_bob_query: Query<(Entity, &Transform, &Size), (With<Bob>, Or<(With<Scouting>, With<Returning>)>)>,
+    idle_bob_query: Query<(Entity, &Transform, &Size), (With<Bob>, With<Idling>)>,
+    map: Res<BattleMap>,
+    mut commands: Commands,
+) {
+    // Get home base data
+    let Some(home_base) = home_base_query.iter().next() else {
+        // No home base found, exit early
+        return;
+    };
+
+    for (entity, behaviour, focus, stats, transform, maybe_stolen, maybe_target, maybe_attack, maybe_movement, maybe_at_target) in enemy_query.iter_mut() {
+        let position = transform.translation.xy();
+        // Snipers keep their distance from whatever they shoot at
+        let standoff = match behaviour {
+            EnemyBehaviour::Sniper => stats.range() * 0.8,
+            _ => 0.0,
+        };
+
+        let (target, position) = match behaviour {
+            // Thieves run off the way they came with their loot
+            EnemyBehaviour::Thief { .. } if maybe_stolen.is_some() => (None, map.nearest_spawn(position)),
+            EnemyBehaviour::Thief { .. } | EnemyBehaviour::Boss { .. } => approach(home_base, 0.0),
+            _ => {
+                let bob = match focus {
+                    EnemyFocus::Base => None,
+                    EnemyFocus::Scouts => nearest_bob(position, field_bob_query.iter()),
+                    EnemyFocus::IdleGrid => nearest_bob(position, idle_bob_query.iter()),
+                };
+                approach(bob.unwrap_or(home_base), standoff)
+            },
+        };
+
+        // Switching targets drops the current attack, a new one starts on arrival
+        if maybe_target.map(|current| current.0) != target {
+            match target {
+                Some(target) => { commands.entity(entity).insert(EnemyTarget(target)); },
+                None => { commands.entity(entity).remove::<EnemyTarget>(); },
+            }
+            if maybe_attack.is_some() {
+                commands.entity(entity).remove::<Attack>();
+            }
+        }
+
+        // Enemies are heavy and take a second to get up to speed
+        let movement = Movement::new(stats.speed, position)
+            .with_acceleration(stats.speed)
+            .with_arrival_radius(ENEMY_ARRIVAL_RADIUS);
+        move_towards(&mut commands, entity, maybe_movement, maybe_at_target, movement);
+    }
+}
+
+fn nearest_bob<'a>(
+    position: Vec2,
+    bobs: impl Iterator<Item = (Entity, &'a Transform, &'a Size)>,
+) -> Option<(Entity, &'a Transform, &'a Size)> {
+    bobs.min_by(|a, b| {
+        let distance_a = a.1.translation.xy().distance(position);
+        let distance_b = b.1.translation.xy().distance(position);
+        distance_a.total_cmp(&distance_b)
+    })
+}
+
+// Attack position on the target's top edge, or `standoff` pixels above it
+fn approach((target, target_transform, target_size): (Entity, &Transform, &Size), standoff: f32) -> (Option<Entity>, Vec2) {
+    let target_pos = target_transform.translation.xy();
+    let position = Vec2::new(
+        target_pos.x,
+        target_pos.y + target_size.0.y / 2.0 + standoff // The top edge
+    );
+    (Some(target), position)
+}
+
+// Enemies start attacking their target once they reach it, thieves steal or escape instead
+pub fn on_enemy_arrived(
+    trigger: On<ArrivedAtTarget>,
+    enemy_query: Query<(&EnemyBehaviour, &EnemyStats, &Name, Option<&EnemyTarget>, Option<&Attack>, Option<&Stolen>), With<Enemy>>,
+    home_base_query: Query<(), With<HomeBase>>,
+    mut inventory_query: Query<&mut ComponentsInventory>,
+    mut commands: Commands,
+) {
+    let Ok((behaviour, stats, name, maybe_target, maybe_attack, maybe_stolen)) = enemy_query.get(trigger.entity) else {
+        return;
+    };
+
+    if let EnemyBehaviour::Thief { steal } = behaviour {
+        match maybe_stolen {
+            Some(stolen) => {
+                commands.log_warning(format!("The {} escaped with {} parts!", name, stolen.0.len()));
+                commands.entity(trigger.entity).despawn();
+            },
+            None => {
+                let Ok(mut inventory) = inventory_query.single_mut() else {
+                    return;
+                };
+                let stolen = inventory.take_parts(*steal);
+                commands.log_warning(format!("A {} stole {} parts!", name, stolen.len()));
+                commands.entity(trigger.entity).insert(Stolen(stolen));
+            },
+        }
+        return;
+    }
+
+    // Start attacking if not already attacking
+    let Some(target) = maybe_target else {
+        return;
+    };
+    if maybe_attack.is_none() {
+        debug!("{} {:?} reached its target at {:?}", name, trigger.entity, trigger.target);
+        if home_base_query.contains(target.0) && matches!(behaviour, EnemyBehaviour::Boss { .. }) {
+            commands.log_warning(format!("The {} reached the home base!", name));
+        }
+        if let Some(attack) = stats.attack(target.0) {
+            commands.entity(trigger.entity).insert(attack);
+        }
+    }
+}
+
+pub fn enemy_stomp_system(
+    mut enemy_query: Query<(Entity, &mut Stomp, &Transform, &Health), With<Enemy>>,
+    bob_query: Query<(), With<Bob>>,
+    spatial: SpatialQuery,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (entity, mut stomp, transform, health) in enemy_query.iter_mut() {
+        if health.is_dead() || !stomp.timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+        let Some(hitbox) = spatial.hitbox(entity) else {
+            continue;
+        };
+        let stunned: Vec<Entity> = spatial
+            .within(hitbox, stomp.radius)
+            .map(|(other, _)| other)
+            .filter(|other| bob_query.contains(*other))
+            .collect();
+        if stunned.is_empty() {
+            continue;
+        }
+
+        for bob in stunned {
+            commands.trigger(ApplyStatusEvent { entity: bob, effect: StatusEffect::stun(stomp.stun_duration).with_source(entity) });
+        }
+        commands.trigger(ApplyStatusEvent { entity, effect: StatusEffect::shield(30.0, 5.0) });
+        commands.floating_text(TextPopup::new("STOMP!", Color::srgb(1.0, 0.4, 0.1), transform.translation).over(entity));
+    }
+}
+
+pub fn boss_summon_system(
+    mut boss_query: Query<(&mut Summoner, &Transform, &Size, &Health), With<Boss>>,
+    minion_query: Query<(), (With<Enemy>, Without<Boss>)>,
+    director: Res<Director>,
+    spawner: EnemySpawner,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (mut summoner, transform, size, health) in boss_query.iter_mut() {
+        if health.is_dead() || !summoner.timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+        if director.mood == DirectorMood::Relax || minion_query.iter().count() >= director.alive_cap || summoner.minions.is_empty() {
+            continue;
+        }
+
+        let id = summoner.minions[summoner.next % summoner.minions.len()].clone();
+        let Some(def) = spawner.get(&id) else {
+            commands.log_error(format!("Unknown minion '{}'", id));
+            continue;
+        };
+        // Alternate between the left and right side of the boss
+        let side = if summoner.next % 2 == 0 { -1.0 } else { 1.0 };
+        let offset = side * (size.0.x + def.size.0) / 2.0 + side * SUMMON_OFFSET;
+        let position = transform.translation.xy() + Vec2::X * offset;
+        spawner.spawn(&mut commands, def, position);
+        summoner.next += 1;
+        commands.log_warning(format!("The boss summoned a {}!", def.name));
+    }
+}
+
+// Defeated minions disappear, a thief drops whatever it stole. The boss stays so the victory screen can take over.
+pub fn enemy_death_system(
+    enemy_query: Query<(Entity, &Health, &Transform, &Name, Option<&Stolen>), (With<Enemy>, Without<Boss>)>,
+    mut inventory_query: Query<&mut ComponentsInventory>,
+    mut commands: Commands,
+) {
+    for (entity, health, transform, name, maybe_stolen) in enemy_query.iter() {
+        if !health.is_dead() {
+            continue;
+        }
+        if let Some(stolen) = maybe_stolen
+            && let Ok(mut inventory) = inventory_query.single_mut()
+        {
+            for part in stolen.0.iter() {
+                inventory.add(*part, 1);
+            }
+            commands.log_info(format!("Recovered {} parts from the {}", stolen.0.len(), name));
+        }
+        commands.floating_text(TextPopup::new("DESTROYED!", Color::srgb(0.8, 0.8, 0.8), transform.translation));
+        commands.entity(entity).despawn();
+    }
+}
@@ -0,0 +1,342 @@
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+const TEXT_OFFSET: Vec3 = Vec3::new(0.0, 50.0, 1.0); // slightly above the entity
+const MERGE_SECONDS: f32 = 0.4; // another hit within this time adds to the number instead of showing a new one
+const STACK_SPACING: f32 = 24.0; // height between texts over the same target
+const MAX_STACK: usize = 4; // older texts are not pushed up any further than this many lines
+const STACK_SMOOTHING: f32 = 12.0; // higher slides texts up faster
+const POOL_LIMIT: usize = 64; // hidden texts kept for reuse, texts beyond this are despawned
+
+/// How a floating text moves, grows and fades. Each curve runs from 0 to 1 over its part of the lifetime
+#[derive(Debug, Clone, Copy)]
+pub struct FloatingTextStyle {
+    pub duration: f32,
+    pub font_size: f32,
+    pub rise: f32, // world units travelled upwards over the duration
+    pub rise_curve: EaseFunction,
+    pub fade_curve: EaseFunction, // 0 is fully visible, 1 is gone
+    pub pop_scale: f32,           // the text starts this big and eases back to its normal size
+    pub pop_seconds: f32,
+    pub pop_curve: EaseFunction,
+}
+
+impl FloatingTextStyle {
+    /// Quick numbers over whatever was hit
+    pub const DAMAGE: Self = Self {
+        duration: 1.0,
+        font_size: 24.0,
+        rise: 60.0,
+        rise_curve: EaseFunction::QuadraticOut,
+        fade_curve: EaseFunction::QuadraticIn,
+        pop_scale: 1.6,
+        pop_seconds: 0.15,
+        pop_curve: EaseFunction::BackOut,
+    };
+
+    /// Short callouts like "STOMP!"
+    pub const CALLOUT: Self = Self {
+        duration: 1.5,
+        font_size: 24.0,
+        rise: 90.0,
+        rise_curve: EaseFunction::SineOut,
+        fade_curve: EaseFunction::CubicIn,
+        pop_scale: 1.3,
+        pop_seconds: 0.2,
+        pop_curve: EaseFunction::QuadraticOut,
+    };
+
+    /// Moments that decide the match, bigger and on screen for longer
+    pub const ANNOUNCEMENT: Self = Self {
+        duration: 3.0,
+        font_size: 40.0,
+        rise: 60.0,
+        rise_curve: EaseFunction::QuadraticOut,
+        fade_curve: EaseFunction::ExponentialIn,
+        pop_scale: 2.0,
+        pop_seconds: 0.5,
+        pop_curve: EaseFunction::ElasticOut,
+    };
+}
+
+/// A floating text to show with `FloatingTextCommands::floating_text`
+#[derive(Debug, Clone)]
+pub struct TextPopup {
+    text: String,
+    color: Color,
+    position: Vec3,
+    style: FloatingTextStyle,
+    target: Option<Entity>,
+    amount: Option<(&'static str, f32)>, // prefix and total of a number that merges with repeats
+}
+
+impl TextPopup {
+    pub fn new(text: impl Into<String>, color: Color, position: Vec3) -> Self {
+        Self {
+            text: text.into(),
+            color,
+            position,
+            style: FloatingTextStyle::CALLOUT,
+            target: None,
+            amount: None,
+        }
+    }
+
+    /// A damage number like "CRIT -12". Numbers with the same prefix over the same target add up while they keep coming quickly
+    pub fn amount(prefix: &'static str, amount: f32, color: Color, position: Vec3) -> Self {
+        Self {
+            amount: Some((prefix, amount)),
+            style: FloatingTextStyle::DAMAGE,
+            ..Self::new(amount_text(prefix, amount), color, position)
+        }
+    }
+
+    pub fn with_style(mut self, style: FloatingTextStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Stacks the text with the other texts over `target` instead of drawing them on top of each other
+    pub fn over(mut self, target: Entity) -> Self {
+        self.target = Some(target);
+        self
+    }
+}
+
+fn amount_text(prefix: &str, amount: f32) -> String {
+    format!("{}-{}", prefix, amount as i32)
+}
+
+#[derive(Component)]
+pub struct FloatingText {
+    style: FloatingTextStyle,
+    color: Color,
+    origin: Vec3,
+    age: f32,
+    stack_offset: f32,
+    target: Option<Entity>,
+    amount: Option<(&'static str, f32)>,
+    expired: bool, // hidden and waiting in the pool
+}
+
+/// Hidden floating texts waiting to be reused, and the texts shown over each target from oldest to newest
+#[derive(Resource, Default)]
+pub struct FloatingTextPool {
+    free: Vec<Entity>,
+    stacks: HashMap<Entity, Vec<Entity>>,
+}
+
+pub trait FloatingTextCommands {
+    fn floating_text(&mut self, popup: TextPopup);
+}
+
+impl FloatingTextCommands for Commands<'_, '_> {
+    fn floating_text(&mut self, popup: TextPopup) {
+        self.queue(move |world: &mut World| show(world, popup));
+    }
+}
+
+fn show(world: &mut World, popup: TextPopup) {
+    world.resource_scope(|world, mut pool: Mut<FloatingTextPool>| {
+        if let (Some(target), Some((prefix, amount))) = (popup.target, popup.amount)
+            && merge(world, &pool, target, prefix, amount, popup.color)
+        {
+            return;
+        }
+
+        let reused = std::iter::from_fn(|| pool.free.pop()).find(|entity| world.get_entity(*entity).is_ok());
+        let entity = reused.unwrap_or_else(|| world.spawn_empty().id());
+        let origin = popup.position + TEXT_OFFSET;
+        world.entity_mut(entity).insert((
+            Text2d::new(popup.text),
+            TextFont {
+                font_size: popup.style.font_size,
+                ..default()
+            },
+            TextColor(popup.color),
+            Transform::from_translation(origin).with_scale(Vec3::splat(popup.style.pop_scale)),
+            Visibility::Visible,
+            FloatingText {
+                style: popup.style,
+                color: popup.color,
+                origin,
+                age: 0.0,
+                stack_offset: 0.0,
+                target: popup.target,
+                amount: popup.amount,
+                expired: false,
+            },
+            Name::new("Floating Text"),
+        ));
+        if let Some(target) = popup.target {
+            pool.stacks.entry(target).or_default().push(entity);
+        }
+    });
+}
+
+// Adds the amount to a recent number with the same prefix and colour over the target, false if there is none
+fn merge(world: &mut World, pool: &FloatingTextPool, target: Entity, prefix: &'static str, amount: f32, color: Color) -> bool {
+    let Some(stack) = pool.stacks.get(&target) else {
+        return false;
+    };
+    for entity in stack.iter().rev() {
+        let Some(mut floating_text) = world.get_mut::<FloatingText>(*entity) else {
+            continue;
+        };
+        let Some((text_prefix, total)) = floating_text.amount else {
+            continue;
+        };
+        if floating_text.expired || floating_text.age > MERGE_SECONDS || text_prefix != prefix || floating_text.color != color {
+            continue;
+        }
+
+        // Start over from where the text is now, so it pops again without jumping back down
+        let style = floating_text.style;
+        let progress = floating_text.age / style.duration;
+        floating_text.origin.y += style.rise * style.rise_curve.sample_clamped(progress);
+        floating_text.age = 0.0;
+        floating_text.amount = Some((prefix, total + amount));
+        if let Some(mut text) = world.get_mut::<Text2d>(*entity) {
+            text.0 = amount_text(prefix, total + amount);
+        }
+        return true;
+    }
+    false
+}
+
+// Rises, pops and fades the texts, then hides them in the pool once they are done
+pub fn floating_text_system(
+    mut query: Query<(Entity, &mut FloatingText, &mut Transform, &mut TextColor, &mut Visibility)>,
+    mut pool: ResMut<FloatingTextPool>,
+    mut commands: Commands,
+    time: Res<Time>,
+) {
+    // Newer texts over a target push the older ones up
+    let stack_offsets: HashMap<Entity, f32> = pool
+        .stacks
+        .values()
+        .flat_map(|stack| stack.iter().rev().enumerate().map(|(line, entity)| (*entity, line.min(MAX_STACK) as f32 * STACK_SPACING)))
+        .collect();
+
+    for (entity, mut floating_text, mut transform, mut text_color, mut visibility) in query.iter_mut() {
+        if floating_text.expired {
+            continue;
+        }
+        floating_text.age += time.delta_secs();
+        let style = floating_text.style;
+        let progress = floating_text.age / style.duration;
+
+        if progress >= 1.0 {
+            if let Some(stack) = floating_text.target.and_then(|target| pool.stacks.get_mut(&target)) {
+                stack.retain(|other| *other != entity);
+            }
+            if pool.free.len() < POOL_LIMIT {
+                floating_text.expired = true;
+                *visibility = Visibility::Hidden;
+                pool.free.push(entity);
+            } else {
+                commands.entity(entity).despawn();
+            }
+            continue;
+        }
+
+        let wanted_offset = stack_offsets.get(&entity).copied().unwrap_or(0.0);
+        let smoothing = (STACK_SMOOTHING * time.delta_secs()).min(1.0);
+        floating_text.stack_offset += (wanted_offset - floating_text.stack_offset) * smoothing;
+
+        let rise = style.rise * style.rise_curve.sample_clamped(progress);
+        transform.translation = floating_text.origin + Vec3::Y * (rise + floating_text.stack_offset);
+
+        let pop = style.pop_curve.sample_clamped(floating_text.age / style.pop_seconds.max(f32::EPSILON));
+        transform.scale = Vec3::splat(style.pop_scale + (1.0 - style.pop_scale) * pop);
+
+        text_color.0 = floating_text.color.with_alpha(1.0 - style.fade_curve.sample_clamped(progress));
+    }
+
+    pool.stacks.retain(|_, stack| !stack.is_empty());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+    use std::time::Duration;
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.init_resource::<FloatingTextPool>();
+        world.init_resource::<Time>();
+        world
+    }
+
+    fn popup(world: &mut World, popup: TextPopup) {
+        world.commands().floating_text(popup);
+        world.flush();
+    }
+
+    fn step(world: &mut World, seconds: f32) {
+        world.resource_mut::<Time>().advance_by(Duration::from_secs_f32(seconds));
+        world.run_system_once(floating_text_system).unwrap();
+    }
+
+    fn texts(world: &mut World) -> Vec<(String, f32)> {
+        world
+            .query::<(&Text2d, &FloatingText)>()
+            .iter(world)
+            .filter(|(_, floating_text)| !floating_text.expired)
+            .map(|(text, floating_text)| (text.0.clone(), floating_text.stack_offset))
+            .collect()
+    }
+
+    #[test]
+    fn quick_repeats_add_up() {
+        let mut world = world();
+        let target = world.spawn_empty().id();
+        let other = world.spawn_empty().id();
+        popup(&mut world, TextPopup::amount("", 5.0, Color::WHITE, Vec3::ZERO).over(target));
+        popup(&mut world, TextPopup::amount("", 7.0, Color::WHITE, Vec3::ZERO).over(target));
+        assert_eq!(texts(&mut world), vec![("-12".to_string(), 0.0)]);
+
+        // Another prefix, another target or a late repeat each get their own text
+        popup(&mut world, TextPopup::amount("CRIT ", 3.0, Color::WHITE, Vec3::ZERO).over(target));
+        popup(&mut world, TextPopup::amount("", 3.0, Color::WHITE, Vec3::ZERO).over(other));
+        step(&mut world, MERGE_SECONDS + 0.1);
+        popup(&mut world, TextPopup::amount("", 1.0, Color::WHITE, Vec3::ZERO).over(target));
+        assert_eq!(texts(&mut world).len(), 4);
+    }
+
+    #[test]
+    fn newer_texts_push_older_ones_up() {
+        let mut world = world();
+        let target = world.spawn_empty().id();
+        popup(&mut world, TextPopup::new("first", Color::WHITE, Vec3::ZERO).over(target));
+        popup(&mut world, TextPopup::new("second", Color::WHITE, Vec3::ZERO).over(target));
+        popup(&mut world, TextPopup::new("elsewhere", Color::WHITE, Vec3::ZERO));
+
+        // A step long enough for the smoothing to reach the wanted offsets right away
+        step(&mut world, 1.0 / STACK_SMOOTHING);
+        let mut texts = texts(&mut world);
+        texts.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            texts,
+            vec![("elsewhere".to_string(), 0.0), ("first".to_string(), STACK_SPACING), ("second".to_string(), 0.0)]
+        );
+    }
+
+    #[test]
+    fn finished_texts_are_pooled_up_to_the_limit() {
+        let mut world = world();
+        for _ in 0..POOL_LIMIT + 2 {
+            popup(&mut world, TextPopup::new("text", Color::WHITE, Vec3::ZERO));
+        }
+        step(&mut world, FloatingTextStyle::CALLOUT.duration);
+        assert_eq!(world.resource::<FloatingTextPool>().free.len(), POOL_LIMIT);
+        assert_eq!(world.query::<&FloatingText>().iter(&world).count(), POOL_LIMIT);
+
+        // New texts reuse the hidden ones instead of spawning
+        popup(&mut world, TextPopup::new("again", Color::WHITE, Vec3::ZERO));
+        assert_eq!(world.resource::<FloatingTextPool>().free.len(), POOL_LIMIT - 1);
+        assert_eq!(world.query::<&FloatingText>().iter(&world).count(), POOL_LIMIT);
+        assert_eq!(texts(&mut world), vec![("again".to_string(), 0.0)]);
+    }
+}
@@ -0,0 +1,291 @@
+use bevy::{input::mouse::AccumulatedMouseScroll, prelude::*};
+use std::ops::Range;
+use crate::bob_state::Idling;
+use crate::{Bob, Size};
+
+const DEFAULT_COLUMNS: usize = 3;
+const DEFAULT_VISIBLE_ROWS: usize = 4;
+const SLOT_PADDING: f32 = 10.0; // padding between bobs
+const FALLBACK_SLOT_SIZE: f32 = 100.0; // used until a member with a Size has joined
+const FORMATION_ORIGIN: Vec2 = Vec2::new(-540.0, 100.0); // centre of the top left slot, left of the home base
+
+/// Layout of the idle Bobs next to the home base, it takes any number of Bobs.
+/// Slots are handed out in join order and the formation re-compacts whenever a Bob leaves.
+/// Every slot has a fixed spot in the world, scrolling only changes which `visible_rows` rows are shown.
+#[derive(Resource)]
+pub struct Formation {
+    pub columns: usize,
+    pub visible_rows: usize,
+    pub origin: Vec2,
+    pub padding: f32,
+    slot_size: Vec2,
+    members: Vec<Entity>,
+    scroll_row: usize,
+}
+
+impl Default for Formation {
+    fn default() -> Self {
+        Self {
+            columns: DEFAULT_COLUMNS,
+            visible_rows: DEFAULT_VISIBLE_ROWS,
+            origin: FORMATION_ORIGIN,
+            padding: SLOT_PADDING,
+            slot_size: Vec2::splat(FALLBACK_SLOT_SIZE),
+            members: Vec::new(),
+            scroll_row: 0,
+        }
+    }
+}
+
+impl Formation {
+    // Add a Bob to the end of the formation, returns its slot
+    pub fn join(&mut self, entity: Entity) -> usize {
+        if let Some(slot) = self.slot_of(entity) {
+            return slot;
+        }
+        self.members.push(entity);
+        self.members.len() - 1
+    }
+
+    // Remove a Bob, everyone behind it moves up one slot
+    pub fn leave(&mut self, entity: Entity) {
+        self.members.retain(|&member| member != entity);
+        self.clamp_scroll();
+    }
+
+    pub fn slot_of(&self, entity: Entity) -> Option<usize> {
+        self.members.iter().position(|&member| member == entity)
+    }
+
+    pub fn members(&self) -> &[Entity] {
+        &self.members
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn rows(&self) -> usize {
+        self.members.len().div_ceil(self.columns.max(1))
+    }
+
+    // Distance between the centres of two neighbouring slots
+    pub fn spacing(&self) -> Vec2 {
+        self.slot_size + Vec2::splat(self.padding)
+    }
+
+    // World position of a slot, rows grow downwards. Scrolling never moves a slot
+    pub fn slot_position(&self, slot: usize) -> Vec2 {
+        let columns = self.columns.max(1);
+        let col = slot % columns;
+        let row = slot / columns;
+        let spacing = self.spacing();
+
+        Vec2::new(
+            self.origin.x + col as f32 * spacing.x,
+            self.origin.y - row as f32 * spacing.y,
+        )
+    }
+
+    pub fn visible_row_range(&self) -> Range<usize> {
+        self.scroll_row..(self.scroll_row + self.visible_rows).min(self.rows())
+    }
+
+    pub fn is_slot_visible(&self, slot: usize) -> bool {
+        let row = slot / self.columns.max(1);
+        row >= self.scroll_row && row < self.scroll_row + self.visible_rows
+    }
+
+    pub fn scroll_by(&mut self, rows: i32) {
+        self.scroll_row = self.scroll_row.saturating_add_signed(rows as isize);
+        self.clamp_scroll();
+    }
+
+    // World space rectangle covered by the visible rows, used for hit testing the scroll wheel
+    pub fn bounds(&self) -> Rect {
+        let spacing = self.spacing();
+        let half_slot = self.slot_size / 2.0;
+        let first_visible = self.slot_position(self.scroll_row * self.columns.max(1));
+        let top_left = first_visible + Vec2::new(-half_slot.x, half_slot.y);
+        let size = Vec2::new(
+            spacing.x * self.columns as f32 - self.padding,
+            spacing.y * self.visible_rows as f32 - self.padding,
+        );
+        Rect::from_corners(top_left, top_left + Vec2::new(size.x, -size.y))
+    }
+
+    fn clamp_scroll(&mut self) {
+        let max_scroll = self.rows().saturating_sub(self.visible_rows);
+        self.scroll_row = self.scroll_row.min(max_scroll);
+    }
+}
+
+// Keeps slot sizes in sync with the biggest member and drops Bobs that no longer exist
+pub fn formation_layout_system(
+    mut formation: ResMut<Formation>,
+    size_query: Query<&Size, With<Bob>>,
+) {
+    let stale: Vec<Entity> = formation
+        .members()
+        .iter()
+        .copied()
+        .filter(|&entity| size_query.get(entity).is_err())
+        .collect();
+    for entity in stale {
+        formation.leave(entity);
+    }
+
+    let slot_size = formation
+        .members()
+        .iter()
+        .filter_map(|&entity| size_query.get(entity).ok())
+        .fold(Vec2::ZERO, |biggest, size| biggest.max(size.0));
+
+    if slot_size != Vec2::ZERO && slot_size != formation.slot_size {
+        formation.slot_size = slot_size;
+    }
+}
+
+// Scrolls the formation when the mouse wheel is used over the idle grid
+pub fn formation_scroll_system(
+    mut formation: ResMut<Formation>,
+    mouse_scroll: Res<AccumulatedMouseScroll>,
+    q_window: Query<&Window>,
+    q_camera: Query<(&Camera, &GlobalTransform)>,
+) {
+    if mouse_scroll.delta.y == 0.0 {
+        return;
+    }
+    let Ok(window) = q_window.single() else {
+        return;
+    };
+    let Ok((camera, camera_transform)) = q_camera.single() else {
+        return;
+    };
+    let Some(cursor_world) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())
+    else {
+        return;
+    };
+
+    if formation.bounds().contains(cursor_world) {
+        // Scrolling up moves back towards the first row
+        formation.scroll_by(-mouse_scroll.delta.y.signum() as i32);
+    }
+}
+
+// Hides idle Bobs whose row is scrolled out of view
+pub fn formation_visibility_system(
+    formation: Res<Formation>,
+    mut query: Query<(Entity, Has<Idling>, &mut Visibility), With<Bob>>,
+) {
+    for (entity, is_idling, mut visibility) in query.iter_mut() {
+        let visible = match (is_idling, formation.slot_of(entity)) {
+            (true, Some(slot)) => formation.is_slot_visible(slot),
+            _ => true,
+        };
+        let wanted = if visible { Visibility::Inherited } else { Visibility::Hidden };
+        if *visibility != wanted {
+            *visibility = wanted;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bob(index: u32) -> Entity {
+        Entity::from_raw_u32(index).unwrap()
+    }
+
+    #[test]
+    fn join_hands_out_slots_in_order() {
+        let mut formation = Formation::default();
+        assert_eq!(formation.join(bob(1)), 0);
+        assert_eq!(formation.join(bob(2)), 1);
+        assert_eq!(formation.join(bob(3)), 2);
+        // Joining again keeps the old slot
+        assert_eq!(formation.join(bob(2)), 1);
+        assert_eq!(formation.len(), 3);
+    }
+
+    #[test]
+    fn join_has_no_cap() {
+        let mut formation = Formation::default();
+        for index in 0..200 {
+            formation.join(bob(index));
+        }
+        assert_eq!(formation.len(), 200);
+        assert_eq!(formation.rows(), 200usize.div_ceil(DEFAULT_COLUMNS));
+    }
+
+    #[test]
+    fn leave_recompacts_the_slots_behind() {
+        let mut formation = Formation::default();
+        for index in 1..=4 {
+            formation.join(bob(index));
+        }
+        formation.leave(bob(2));
+
+        assert_eq!(formation.members(), &[bob(1), bob(3), bob(4)]);
+        assert_eq!(formation.slot_of(bob(3)), Some(1));
+        assert_eq!(formation.slot_of(bob(4)), Some(2));
+        assert_eq!(formation.slot_of(bob(2)), None);
+    }
+
+    #[test]
+    fn slot_position_fills_rows_left_to_right() {
+        let formation = Formation::default();
+        let spacing = formation.spacing();
+
+        assert_eq!(formation.slot_position(0), FORMATION_ORIGIN);
+        assert_eq!(formation.slot_position(1), FORMATION_ORIGIN + Vec2::new(spacing.x, 0.0));
+        assert_eq!(formation.slot_position(DEFAULT_COLUMNS), FORMATION_ORIGIN - Vec2::new(0.0, spacing.y));
+    }
+
+    #[test]
+    fn scrolling_does_not_move_slots() {
+        let mut formation = Formation::default();
+        for index in 0..30 {
+            formation.join(bob(index));
+        }
+        let before = formation.slot_position(7);
+        formation.scroll_by(2);
+
+        assert_eq!(formation.slot_position(7), before);
+        assert_eq!(formation.visible_row_range(), 2..2 + DEFAULT_VISIBLE_ROWS);
+        assert!(!formation.is_slot_visible(0));
+        assert!(formation.is_slot_visible(2 * DEFAULT_COLUMNS));
+    }
+
+    #[test]
+    fn scroll_is_clamped_after_leaving() {
+        let mut formation = Formation::default();
+        for index in 0..30 {
+            formation.join(bob(index));
+        }
+        formation.scroll_by(100);
+        assert_eq!(formation.visible_row_range().end, formation.rows());
+
+        for index in 6..30 {
+            formation.leave(bob(index));
+        }
+        assert_eq!(formation.visible_row_range(), 0..2);
+    }
+
+    #[test]
+    fn bounds_follow_the_visible_rows() {
+        let mut formation = Formation::default();
+        for index in 0..30 {
+            formation.join(bob(index));
+        }
+        let first_visible = formation.slot_position(0);
+        assert!(formation.bounds().contains(first_visible));
+
+        formation.scroll_by(3);
+        assert!(!formation.bounds().contains(first_visible));
+        assert!(formation.bounds().contains(formation.slot_position(3 * DEFAULT_COLUMNS)));
+    }
+}
@@ -7,7 +7,7 @@ pub struct Loot {
     pub quantity: u32,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum LootType {
     Head,
     Arms,
@@ -15,6 +15,19 @@ pub enum LootType {
     Legs,
 }
 
+impl LootType {
+    pub const ALL: [LootType; 4] = [LootType::Head, LootType::Body, LootType::Arms, LootType::Legs];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            LootType::Head => "Heads",
+            LootType::Arms => "Arms",
+            LootType::Body => "Bodies",
+            LootType::Legs => "Legs",
+        }
+    }
+}
+
 impl Loot {
     pub fn new(loot_type: LootType, quantity: u32) -> Self {
         Self {
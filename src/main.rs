@@ -1,40 +1,77 @@
-use bevy::{ecs::component, input_focus::InputFocus, log::tracing_subscriber::fmt::time, prelude::*};
+use bevy::{ecs::{component, query::QueryData}, input_focus::InputFocus, log::tracing_subscriber::fmt::time, prelude::*};
 use bevy_inspector_egui::{bevy_egui::EguiPlugin, quick::WorldInspectorPlugin};
 //use bevy::picking::pointer::PointerInteraction; Useful for selectable meshes
+use serde::Deserialize;
 use std::process;
 
 mod ui;
 use ui::*;
 mod loot;
 use loot::*;
-
-// Floating text components
-#[derive(Component)]
-struct FloatingText {
-    timer: Timer,
-    velocity: Vec3,
-}
-
-#[derive(Component)]
-struct DamageText;
-
-impl FloatingText {
-    fn new(duration: f32) -> Self {
-        Self {
-            timer: Timer::from_seconds(duration, TimerMode::Once),
-            velocity: Vec3::new(0.0, 100.0, 0.0), // Float upward
-        }
-    }
-}
+mod difficulty;
+use difficulty::*;
+mod formation;
+use formation::*;
+mod navigation;
+use navigation::*;
+mod bob_state;
+use bob_state::*;
+mod message_log;
+use message_log::*;
+mod repair;
+use repair::*;
+mod turret;
+use turret::*;
+mod upgrades;
+use upgrades::*;
+mod projectile;
+use projectile::*;
+mod collision;
+use collision::*;
+mod damage;
+use damage::*;
+mod status_effects;
+use status_effects::*;
+mod enemies;
+use enemies::*;
+mod boss;
+use boss::*;
+mod director;
+use director::*;
+mod map;
+use map::*;
+mod camera;
+use camera::*;
+mod animation;
+use animation::*;
+mod assembly;
+use assembly::*;
+mod floating_text;
+use floating_text::*;
+mod audio;
+use audio::*;
+mod run_stats;
+use run_stats::*;
 
 const NORMAL_ATTACK: Color = Color::srgb(1.0,0.0, 0.0);
 const NORMAL_BUILD: Color = Color::srgb(0.9,0.3, 0.0);
 const NORMAL_SCOUT: Color = Color::srgb(0.0, 0.0, 1.0);
+const NORMAL_REPAIR: Color = Color::srgb(0.1, 0.6, 0.2);
+const NORMAL_UPGRADES: Color = Color::srgb(0.5, 0.2, 0.7);
+const NORMAL_TURRET: Color = Color::srgb(0.4, 0.45, 0.5);
+const NORMAL_SETTINGS: Color = Color::srgb(0.3, 0.3, 0.35);
 const HOVER_COLOR: Color =  Color::WHITE;
+const SEPARATION_WEIGHT: f32 = 1.5; // how strongly moving units steer away from each other
+const DEFAULT_ARRIVAL_RADIUS: f32 = 2.0;
+const DEFAULT_ACCELERATION_FACTOR: f32 = 4.0; // reach top speed in a quarter of a second
+const MELEE_RANGE: f32 = 30.0;
+const BOB_RANGED_RANGE: f32 = 250.0;
+const BOSS_ID: &str = "warlord";
 const HEAD_COLOR: Color = Color::srgb(0.0, 0.0, 1.0); //Removed later when not just squares
 
-#[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[derive(States, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 enum GameStates {
+    ChoosingDifficulty, // skipped when the difficulty comes from the command line
     #[default]
     Playing,
     Win,
@@ -42,38 +79,10 @@ enum GameStates {
 }
 
 
-#[derive(Resource)]
-struct GridState {
-    occupied_positions: Vec<bool>,  // Track which positions are occupied
-} 
-
-impl Default for GridState {
-    fn default() -> Self {
-        Self { 
-            occupied_positions: vec![false; 50]  // Support up to 50 grid positions
-        }
-    }
-}
-
-impl GridState {
-    // Find the first available (unoccupied) grid position
-    fn find_first_available(&self) -> Option<usize> {
-        self.occupied_positions.iter().position(|&occupied| !occupied)
-    }
-    
-    // Mark a position as occupied
-    fn occupy(&mut self, position: usize) {
-        if position < self.occupied_positions.len() {
-            self.occupied_positions[position] = true;
-        }
-    }
-    
-    // Mark a position as free
-    fn free(&mut self, position: usize) {
-        if position < self.occupied_positions.len() {
-            self.occupied_positions[position] = false;
-        }
-    }
+// Time spent in the current match, stops counting once the game is won or lost
+#[derive(Resource, Default)]
+struct MatchClock {
+    elapsed: f32,
 }
 
 #[derive(Event)]
@@ -98,13 +107,79 @@ struct Legs;
 struct Arms;
 
 
-#[derive(Component)]
+// Parts on hand per type. Building, repairs and upgrades use up any part, always of the type there is most of
+#[derive(Component, Default)]
 struct ComponentsInventory {
-    count: u32,
+    heads: u32,
+    bodies: u32,
+    arms: u32,
+    legs: u32,
 }
 
-//#[derive(Component)]
-//struct Selected;
+impl ComponentsInventory {
+    // Starting parts are spread evenly over the types
+    fn with_starting_parts(count: u32) -> Self {
+        let mut inventory = Self::default();
+        for part in LootType::ALL.iter().cycle().take(count as usize) {
+            inventory.add(*part, 1);
+        }
+        inventory
+    }
+
+    fn count(&self) -> u32 {
+        self.heads + self.bodies + self.arms + self.legs
+    }
+
+    fn get(&self, part: LootType) -> u32 {
+        match part {
+            LootType::Head => self.heads,
+            LootType::Body => self.bodies,
+            LootType::Arms => self.arms,
+            LootType::Legs => self.legs,
+        }
+    }
+
+    fn add(&mut self, part: LootType, quantity: u32) {
+        *self.stock_mut(part) += quantity;
+    }
+
+    // Removes `count` parts, takes nothing if there aren't enough
+    fn take(&mut self, count: u32) -> bool {
+        if self.count() < count {
+            return false;
+        }
+        self.take_parts(count);
+        true
+    }
+
+    // Removes up to `count` parts and returns their types
+    fn take_parts(&mut self, count: u32) -> Vec<LootType> {
+        let mut taken = Vec::new();
+        for _ in 0..count {
+            let most = LootType::ALL.into_iter().max_by_key(|part| self.get(*part)).unwrap_or(LootType::Head);
+            let stock = self.stock_mut(most);
+            if *stock == 0 {
+                break;
+            }
+            *stock -= 1;
+            taken.push(most);
+        }
+        taken
+    }
+
+    fn stock_mut(&mut self, part: LootType) -> &mut u32 {
+        match part {
+            LootType::Head => &mut self.heads,
+            LootType::Body => &mut self.bodies,
+            LootType::Arms => &mut self.arms,
+            LootType::Legs => &mut self.legs,
+        }
+    }
+}
+
+// The Bob last clicked on
+#[derive(Component)]
+struct Selected;
 
 #[derive(Component)]
 struct Enemy;
@@ -112,17 +187,10 @@ struct Enemy;
 #[derive(Component)]
 struct HomeBase;
 
+// What a Bob is doing is its state component, only change it through set_bob_state
 #[derive(Component)]
-struct Bob{
-    state: BobState,
-    grid_position: usize,  // Each Bob remembers its own grid slot
-}
+struct Bob;
 
-enum BobState {
-    Attacking,
-    Idling,
-    Scouting,
-}
 
 #[derive(Component)]
 struct Health {
@@ -148,11 +216,16 @@ impl Health {
 
 }
 
+
 #[derive(Component)]
 enum MenuButton {
     Attack,
     Build,
     Scout,
+    Repair,
+    Upgrades,
+    Turret,
+    Settings,
 }
 
 
@@ -161,7 +234,43 @@ struct OriginalColor(Color);
 
 #[derive(Component)]
 struct Movement {
-    speed: f32,
+    speed: f32,           // top speed in pixels per second
+    target: Vec2,
+    acceleration: f32,    // pixels per second squared, also used for braking
+    arrival_radius: f32,  // close enough to count as arrived
+    current_speed: f32,
+}
+
+impl Movement {
+    fn new(speed: f32, target: Vec2) -> Self {
+        Self {
+            speed,
+            target,
+            acceleration: speed * DEFAULT_ACCELERATION_FACTOR,
+            arrival_radius: DEFAULT_ARRIVAL_RADIUS,
+            current_speed: 0.0,
+        }
+    }
+
+    fn with_acceleration(mut self, acceleration: f32) -> Self {
+        self.acceleration = acceleration;
+        self
+    }
+
+    fn with_arrival_radius(mut self, arrival_radius: f32) -> Self {
+        self.arrival_radius = arrival_radius;
+        self
+    }
+}
+
+// Where a unit ended up after its last Movement finished, so it is not sent to the same spot again
+#[derive(Component)]
+struct AtTarget(Vec2);
+
+// Triggered by movement_system when a unit reaches its Movement target, the Movement is removed at that point
+#[derive(EntityEvent)]
+struct ArrivedAtTarget {
+    entity: Entity,
     target: Vec2,
 }
 
@@ -171,9 +280,96 @@ struct Scout;
 #[derive(Component)]
 struct Attack{
     target_entity: Entity,  // Reference to the enemy entity
-    damage: f32,
+    damage: Damage,
     max_cooldown: f32,
     current_cooldown: f32,
+    range: f32,                     // how close the target's edge has to be before attacking
+    projectile_speed: Option<f32>,  // ranged attacks fire a projectile instead of hitting instantly
+}
+
+impl Attack {
+    fn melee(target_entity: Entity, damage: Damage, max_cooldown: f32) -> Self {
+        Self {
+            target_entity,
+            damage,
+            max_cooldown,
+            current_cooldown: 0.0,  // Start at 0 to attack immediately
+            range: MELEE_RANGE,
+            projectile_speed: None,
+        }
+    }
+
+    fn ranged(target_entity: Entity, damage: Damage, max_cooldown: f32, range: f32, projectile_speed: f32) -> Self {
+        Self {
+            range,
+            projectile_speed: Some(projectile_speed),
+            ..Self::melee(target_entity, damage, max_cooldown)
+        }
+    }
+}
+
+// An attack that has started but only lands on the "hit" frame of the attacker's Attack animation
+#[derive(Component)]
+struct PendingHit {
+    target_entity: Entity,
+    damage: Damage,
+    projectile_speed: Option<f32>,
+}
+
+// What a Bob fights with, picked in the builder
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum ArmsKind {
+    #[default]
+    Melee,
+    Ranged,
+    Cryo, // special ranged arms that slow what they hit
+}
+
+impl ArmsKind {
+    fn label(&self) -> &'static str {
+        match self {
+            ArmsKind::Melee => "Melee",
+            ArmsKind::Ranged => "Ranged",
+            ArmsKind::Cryo => "Cryo",
+        }
+    }
+
+    fn next(&self) -> Self {
+        match self {
+            ArmsKind::Melee => ArmsKind::Ranged,
+            ArmsKind::Ranged => ArmsKind::Cryo,
+            ArmsKind::Cryo => ArmsKind::Melee,
+        }
+    }
+
+    fn attack(&self, target_entity: Entity) -> Attack {
+        match self {
+            ArmsKind::Melee => Attack::melee(target_entity, Damage::new(10.0, DamageType::Kinetic).with_crit(0.2, 2.0), 1.0),
+            // Zapper arms, the enemy is weak to electric damage
+            ArmsKind::Ranged => Attack::ranged(target_entity, Damage::new(6.0, DamageType::Electric).with_crit(0.1, 2.0), 1.2, BOB_RANGED_RANGE, 400.0),
+            ArmsKind::Cryo => {
+                let damage = Damage::new(3.0, DamageType::Kinetic).with_status(StatusEffect::slow(0.5, 3.0));
+                Attack::ranged(target_entity, damage, 1.5, BOB_RANGED_RANGE, 350.0)
+            },
+        }
+    }
+
+    // Melee arms come plated, the ranged ones are insulated against what their own kind of weapon deals
+    fn defences(&self) -> (Armour, Resistances) {
+        match self {
+            ArmsKind::Melee => (Armour(0.2), Resistances { kinetic: 0.2, ..default() }),
+            ArmsKind::Ranged => (Armour(0.1), Resistances { electric: 0.5, ..default() }),
+            ArmsKind::Cryo => (Armour(0.1), Resistances { fire: 0.3, ..default() }),
+        }
+    }
+
+    // How far from the enemy's edge the Bob stands while attacking
+    fn standoff(&self) -> f32 {
+        match self {
+            ArmsKind::Melee => 0.0,
+            ArmsKind::Ranged | ArmsKind::Cryo => BOB_RANGED_RANGE * 0.8,
+        }
+    }
 }
 
 #[derive(Component)]
@@ -190,69 +386,166 @@ impl Size {
 }
 
 fn main() {
+    let mut startup_warnings = Vec::new();
+    let difficulty = Difficulty::from_args(std::env::args(), &mut startup_warnings);
+    let first_state = if difficulty.is_some() { GameStates::Playing } else { GameStates::ChoosingDifficulty };
+    let map = BattleMap::from_args(std::env::args(), &mut startup_warnings);
+    let ui_settings = UiSettings::from_args(std::env::args(), &mut startup_warnings);
+
     App::new()
         .add_plugins((DefaultPlugins, MeshPickingPlugin))
         .add_plugins(EguiPlugin::default())
 
-        .init_state::<GameStates>()
+        .insert_state(first_state)
         // .add_plugins(bevy_inspector_egui::quick::WorldInspectorPlugin::new())
         .init_resource::<InputFocus>()
-        .init_resource::<GridState>()
+        .init_resource::<Formation>()
+        .init_resource::<NavGrid>()
+        .init_resource::<MatchClock>()
+        .init_resource::<MessageLog>()
+        .init_resource::<BaseRepair>()
+        .init_resource::<TechTree>()
+        .init_resource::<EnemyRoster>()
+        .init_resource::<Director>()
+        .init_resource::<SpriteSheets>()
+        .init_resource::<FloatingTextPool>()
+        .init_resource::<AudioLibrary>()
+        .add_observer(on_game_log)
+        .add_observer(on_game_log_toast)
+        .insert_resource(difficulty.unwrap_or_default())
+        .insert_resource(StartupWarnings(startup_warnings))
+        .insert_resource(map)
+        .insert_resource(ui_settings)
+        .insert_resource(VolumeSettings::load())
         .add_observer(on_build_bob)
         .add_observer(on_reset_ui)
         .add_observer(on_attack)
         .add_observer(on_scout)
-        .add_systems(OnEnter(GameStates::Win), win_screen)
-        .add_systems(OnEnter(GameStates::Loss), loss_screen)
+        .add_observer(on_repair_base)
+        .add_observer(on_purchase_upgrade)
+        .add_observer(on_build_turret)
+        .add_observer(on_toggle_tech_tree)
+        .add_observer(on_bob_arrived)
+        .add_observer(on_enter_idling)
+        .add_observer(on_exit_idling)
+        .add_observer(on_exit_attacking)
+        .add_observer(on_exit_scouting)
+        .add_observer(on_exit_moving)
+        .add_observer(on_enter_dead)
+        .add_observer(on_enemy_arrived)
+        .add_observer(on_damage)
+        .add_observer(on_apply_status)
+        .add_observer(on_director_damage)
+        .add_observer(on_hurt_animation)
+        .add_observer(on_animation_event)
+        .add_observer(on_attack_hit)
+        .add_observer(on_toggle_settings)
+        .add_observer(on_play_sound)
+        .add_observer(on_hit_sound)
+        .add_systems(OnEnter(GameStates::ChoosingDifficulty), difficulty_screen)
+        .add_systems(OnExit(GameStates::ChoosingDifficulty), cleanup_difficulty_screen)
+        // Everything that depends on the difficulty is spawned once it is known
+        .add_systems(OnEnter(GameStates::Playing), (setup, test_data))
+        .add_systems(OnEnter(GameStates::Win), (win_screen, record_run_system))
+        .add_systems(OnEnter(GameStates::Loss), (loss_screen, record_run_system))
         .add_systems(OnExit(GameStates::Win), cleanup_win_screen)
         .add_systems(OnExit(GameStates::Loss), cleanup_loss_screen)
-        .add_systems(Startup, (setup, test_data, setup_build_bob_ui))
+        .add_systems(Startup, (setup_camera, setup_map, setup_sprite_sheets, setup_build_bob_ui, setup_idle_grid_ui, setup_hud, setup_message_log_ui, setup_tech_tree_ui, setup_boss_bar, setup_minimap, setup_settings_ui, setup_audio, report_startup_warnings.after(setup_message_log_ui)))
         .add_systems(Update, (
             button_system, 
             bob_system, 
             build_bob_ui_system,
+            arms_toggle_system,
+            nav_grid_rebuild_system.before(path_planning_system),
+            path_planning_system.before(movement_system),
             movement_system, 
             scouting_system, 
             attacking_system,
-            enemy_system,
             play_again_button_system,
+            difficulty_button_system.run_if(in_state(GameStates::ChoosingDifficulty)),
             floating_text_system,
+            formation_layout_system,
+            formation_scroll_system,
+            formation_visibility_system,
+            idle_grid_ui_system,
+            camera_zoom_system.before(camera_pan_system),
+            camera_pan_system,
+            background_system.after(camera_pan_system),
+        ))
+        .add_systems(Update, (
+            bob_health_system,
+            bob_repair_system,
+            dead_bob_system,
+            match_clock_system.run_if(in_state(GameStates::Playing)),
+            hud_system,
+            message_log_ui_system,
+            message_log_scroll_system,
+            toast_system,
+            base_repair_system.run_if(in_state(GameStates::Playing)),
+            repair_button_system,
+            turret_targeting_system.before(attacking_system),
+            turret_health_system,
+            projectile_system,
+            collision_resolution_system.after(movement_system),
+            tech_tree_button_system,
+            tech_tree_ui_system,
+            ui_scale_settings_system.before(ui_scale_system),
+            ui_scale_system,
+        ))
+        .add_systems(Update, (
+            enemy_system,
+            enemy_stomp_system,
+            boss_summon_system,
+            enemy_death_system,
+            status_effect_system,
+            status_icon_system,
+            boss_phase_system,
+            area_attack_system,
+            boss_bar_system,
+            director_system.run_if(in_state(GameStates::Playing)),
+            minimap_marker_system,
+            minimap_viewport_system.after(camera_pan_system),
+            animation_state_system.after(attacking_system),
+            animation_system.after(animation_state_system),
+            bob_part_animation_system.after(animation_state_system),
+            button_sound_system,
+            state_audio_system.run_if(state_changed::<GameStates>),
+            music_volume_system,
+            volume_slider_ui_system,
         ))
         .run();
 }
 
 
+fn match_clock_system(mut match_clock: ResMut<MatchClock>, time: Res<Time>) {
+    match_clock.elapsed += time.delta_secs();
+}
+
 //TODO, DELETE THIS LATER, ONLY FOR TESTING
-fn test_data(mut commands:Commands) {
-    commands.spawn(ComponentsInventory { count: 10 });
+fn test_data(mut commands:Commands, difficulty: Res<Difficulty>) {
+    commands.spawn(ComponentsInventory::with_starting_parts(difficulty.starting_parts));
 }
 
-// Floating text system function
-fn spawn_floating_text(
-    commands: &mut Commands,
-    position: Vec3,
-    text: String,
-    color: Color,
-) {
+// The camera is up before the run starts so the difficulty screen can be drawn
+fn setup_camera(mut commands: Commands, map: Res<BattleMap>) {
+    // Camera starts over the base
     commands.spawn((
-        Text2d::new(text),
-        TextFont {
-            font_size: 24.0,
-            ..default()
-        },
-        TextColor(color),
-        Transform::from_translation(position + Vec3::new(0.0, 50.0, 1.0)), // Slightly above the entity
-        FloatingText::new(2.0), // Show for 2 seconds
-        DamageText,
+        Camera2d,
+        logical_projection(),
+        CameraController::default(),
+        Transform::from_xyz(map.base.x, map.base.y, 0.0),
     ));
 }
 
-fn setup(mut commands: Commands, q_window: Query<&Window>, asset_server: Res<AssetServer>) {
-    commands.spawn(Camera2d); //camera setup
-
-    // Spawn an enemy entity
-    let enemy_size = Size::new(200.0, 200.0);
-    let enemy_size_vec = enemy_size.0;  // Extract Vec2 before moving
+fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    difficulty: Res<Difficulty>,
+    roster: Res<EnemyRoster>,
+    map: Res<BattleMap>,
+    mut formation: ResMut<Formation>,
+) {
+    commands.log_info(format!("Starting run on {} difficulty", difficulty.preset.label()));
 
     let homeBase_size = Size::new(300.0, 300.0);
     let homeBase_size_vec = homeBase_size.0;
@@ -264,57 +557,59 @@ fn setup(mut commands: Commands, q_window: Query<&Window>, asset_server: Res<Ass
             custom_size: Some(homeBase_size_vec),
             ..default()
         },
-        Transform::from_xyz(0.0, 50.0, 1.0),
+        Transform::from_xyz(map.base.x, map.base.y, 1.0),
         Name::new("Home Base"),
-        Health::new(500.0),
+        Health::new(difficulty.home_base_max_health()),
+        Obstacle,
+        Collider::solid(),
     ));
 
-        commands.spawn((
-        Enemy,
-        enemy_size,
-        Sprite{ 
-            image: asset_server.load("sprites/Enemy.png"),
-            custom_size: Some(enemy_size_vec),
-            ..default()
-        },
-        Transform::from_xyz(0.0, 320.0, 1.0),
-        Health::new(1000.0),
-        Name::new("Enemy"),
-    ));
+    // Idle Bobs line up next to wherever the base ended up
+    formation.origin = map.formation_origin;
+
+    // The boss, it brings its own minions
+    match roster.get(BOSS_ID) {
+        Some(boss) => { spawn_enemy(&mut commands, boss, map.boss_spawn, &difficulty, &asset_server); },
+        None => commands.log_error(format!("No '{}' in enemies.ron", BOSS_ID)),
+    }
 
     //Buttons setup
-    let mut root = commands.spawn(
+    let mut root = commands.spawn((
         Node {
             width: Val::Percent(100.0),
             height: Val::Percent(50.0),
-            flex_direction: FlexDirection::Column,
+            flex_direction: FlexDirection::Row, // one row, the menu got too tall for a column
             align_items: AlignItems::Center,
-            justify_content: JustifyContent::SpaceEvenly,
+            justify_content: JustifyContent::Center,
+            column_gap: Val::Px(15.0),
             position_type: PositionType::Absolute,
             bottom: Val::Px(0.0),
             ..default()
-        }
-    );    
+        },
+        Pickable::IGNORE, // only the buttons take the mouse, the rest of the bar belongs to the battlefield
+    ));    
     
     // spawn_sprite(&mut root, 100.0, 100.0, Color::srgb(0.8, 0.2, 0.2));
     
     spawn_button(&mut root, MenuButton::Attack, "Attack!", NORMAL_ATTACK);
     spawn_button(&mut root, MenuButton::Build,  "Build!",  NORMAL_BUILD);
     spawn_button(&mut root, MenuButton::Scout,  "Scout!",  NORMAL_SCOUT);
+    spawn_button(&mut root, MenuButton::Repair, "Repair!", NORMAL_REPAIR);
+    spawn_button(&mut root, MenuButton::Upgrades, "Upgrades", NORMAL_UPGRADES);
+    spawn_button(&mut root, MenuButton::Turret, "Turret!", NORMAL_TURRET);
+    spawn_button(&mut root, MenuButton::Settings, "Settings", NORMAL_SETTINGS);
     
-    // Spawn fullscreen background sprite
-    if let Ok(window) = q_window.single() {
-        let sprite_size = Vec2::new(window.width(), window.height());
-        commands.spawn((
-            Sprite {
-                image: asset_server.load("sprites/Background.png"),
-                custom_size: Some(sprite_size),
-                ..default()
-            },
-            Transform::from_xyz(0.0, 0.0, 0.0), // Centered, behind everything (z=0)
-            Name::new("Background"),
-        ));
-    }
+    // Background covering the map, background_system grows it with the view
+    commands.spawn((
+        Background,
+        Sprite {
+            image: asset_server.load("sprites/Background.png"),
+            custom_size: Some(map.bounds.size()),
+            ..default()
+        },
+        Transform::from_xyz(0.0, 0.0, 0.0), // Behind everything (z=0)
+        Name::new("Background"),
+    ));
 }
 
 
@@ -376,9 +671,13 @@ fn button_system(
                 *bg_color = click_color.into();
 
                 match button_type {
-                    MenuButton::Attack => {commands.trigger(StartAttackingEvent); println!("Clicked on Attack");},
-                    MenuButton::Build => {commands.trigger(BuildBobEvent); println!("Clicked on build");},
-                    MenuButton::Scout => {commands.trigger(StartScoutingEvent); println!("Clicked on Scout");},
+                    MenuButton::Attack => {commands.trigger(StartAttackingEvent); debug!("Clicked on Attack");},
+                    MenuButton::Build => {commands.trigger(BuildBobEvent); debug!("Clicked on build");},
+                    MenuButton::Scout => {commands.trigger(StartScoutingEvent); debug!("Clicked on Scout");},
+                    MenuButton::Repair => {commands.trigger(RepairBaseEvent); debug!("Clicked on Repair");},
+                    MenuButton::Upgrades => {commands.trigger(ToggleTechTreeEvent); debug!("Clicked on Upgrades");},
+                    MenuButton::Turret => {commands.trigger(BuildTurretEvent); debug!("Clicked on Turret");},
+                    MenuButton::Settings => {commands.trigger(ToggleSettingsEvent); debug!("Clicked on Settings");},
                 }
             },
 
@@ -397,368 +696,483 @@ fn button_system(
 }
 
 fn movement_system(
-    mut query: Query<(Entity, &mut Transform, &Movement)>,
+    mut query: Query<(Entity, &mut Transform, &mut Movement, Option<&mut NavPath>, Option<&Size>, Option<&StatusEffects>)>,
     time: Res<Time>, 
+    mut commands: Commands,
 ) {    
-    for (_entity, mut transform, movement) in query.iter_mut() {
+    // Snapshot of every moving unit so they can steer away from each other
+    let neighbours: Vec<(Entity, Vec2, f32)> = query
+        .iter()
+        .map(|(entity, transform, _, _, maybe_size, _)| {
+            (entity, transform.translation.xy(), maybe_size.map_or(0.0, |size| size.0.min_element() / 2.0))
+        })
+        .collect();
+
+    for (entity, mut transform, mut movement, maybe_path, maybe_size, maybe_effects) in query.iter_mut() {
+        let current_position = transform.translation.xy();
+        let distance_to_target = movement.target.distance(current_position);
+
+        // Arrived: snap onto the target, stop and let the unit logic react
+        if distance_to_target <= movement.arrival_radius {
+            transform.translation.x = movement.target.x;
+            transform.translation.y = movement.target.y;
+            let target = movement.target;
+            commands.entity(entity).remove::<Movement>().insert(AtTarget(target));
+            commands.trigger(ArrivedAtTarget { entity, target });
+            continue;
+        }
+
+        // Follow the planned path first, the last leg goes straight to the target
+        let waypoint = match maybe_path {
+            Some(mut path) => {
+                path.advance(current_position);
+                path.next_waypoint().unwrap_or(movement.target)
+            }
+            None => movement.target,
+        };
+
+        // Accelerate up to top speed, braking early enough to stop on the target instead of overshooting it.
+        // Slows lower the top speed, stuns hold the unit in place
+        let top_speed = movement.speed * maybe_effects.map_or(1.0, StatusEffects::speed_multiplier);
+        let braking_speed = (2.0 * movement.acceleration * distance_to_target).sqrt();
+        movement.current_speed = (movement.current_speed + movement.acceleration * time.delta_secs())
+            .min(top_speed)
+            .min(braking_speed);
+
+        let distance = waypoint.distance(current_position);
+        if distance > 0.0 {
+            let seek = (waypoint-current_position).normalize();
+
+            // Push away from overlapping units, fading out near the final target so units can still arrive
+            let radius = maybe_size.map_or(0.0, |size| size.0.min_element() / 2.0);
+            let mut separation = Vec2::ZERO;
+            for (other, other_position, other_radius) in neighbours.iter() {
+                if *other == entity {
+                    continue;
+                }
+                let offset = current_position - *other_position;
+                let min_distance = radius + other_radius;
+                let overlap = min_distance - offset.length();
+                if overlap > 0.0 && min_distance > 0.0 {
+                    separation += offset.normalize_or(Vec2::X) * (overlap / min_distance);
+                }
+            }
+            let fade = (distance_to_target / (radius * 2.0).max(1.0)).min(1.0);
+            let direction = (seek + separation * SEPARATION_WEIGHT * fade).normalize_or(seek);
 
-        let movement_speed = movement.speed; // pixels per second
-        let target = movement.target;
+            // Never step past the waypoint in a single frame
+            let step = (movement.current_speed * time.delta_secs()).min(distance);
+            transform.translation.x += direction.x * step;
+            transform.translation.y += direction.y * step;
+        }
+    }
+}
 
-        let current_position = transform.translation.xy();
-        let distance = target.distance(current_position);
-        if distance >= 2.0 {
-            let direction = (target-current_position).normalize();
-            transform.translation.x += direction.x * movement_speed * time.delta_secs();
-            transform.translation.y += direction.y * movement_speed * time.delta_secs();
+// Send a unit towards the target of `movement`, updating its current Movement if it is already walking.
+// Units that already arrived at that spot (within the arrival radius) are left alone.
+fn move_towards(
+    commands: &mut Commands,
+    entity: Entity,
+    maybe_movement: Option<Mut<Movement>>,
+    maybe_at_target: Option<&AtTarget>,
+    movement: Movement,
+) {
+    if let Some(mut current) = maybe_movement {
+        if current.target != movement.target {
+            current.target = movement.target;
         }
+        return;
     }
+
+    if maybe_at_target.is_some_and(|at_target| at_target.0.distance(movement.target) <= movement.arrival_radius) {
+        return;
+    }
+
+    commands.entity(entity).remove::<AtTarget>().insert(movement);
 }
 
 fn scouting_system(
-    mut query: Query<(Entity, &mut Bob, Option<&Scout>), With<Head>>,
+    query: Query<Entity, (With<Scouting>, With<Scout>)>,
     mut inventory_query: Query<&mut ComponentsInventory>,
     mut commands: Commands,
-    mut grid_state: ResMut<GridState>,
+    difficulty: Res<Difficulty>,
+    tech_tree: Res<TechTree>,
 ) {
-    for (entity, mut bob, maybe_scout) in query.iter_mut() {
-        if matches!(bob.state, BobState::Scouting) {
-            // When scout component is added, the bob has reached the target
-            if maybe_scout.is_some() {
-                // Generate random loot!
-                let loot = generate_random_loot();
-                println!("Scout {:?} found loot: {:?} x{}", entity, loot.loot_type, loot.quantity);
-                
-                // TODO: Add loot to inventory
-                // For now, just attach it as a component to the bob
-                commands.entity(entity).insert(loot);
-                // add to the component inventory
-                let mut inventory = inventory_query.single_mut().unwrap();
-                inventory.count += 3;
-
-                commands.entity(entity).remove::<Scout>();
-                
-                // Find first available grid position and assign it
-                if let Some(new_grid_pos) = grid_state.find_first_available() {
-                    bob.grid_position = new_grid_pos;
-                    grid_state.occupy(new_grid_pos);
-                    bob.state = BobState::Idling;
-                } else {
-                    println!("Warning: No available grid position for returning scout!");
-                }
-            }
+    // When scout component is added, the bob has reached the target
+    for entity in query.iter() {
+        // Generate random loot and add it to the component inventory
+        let mut inventory = inventory_query.single_mut().unwrap();
+        for loot in generate_loot_batch(difficulty.scouting_yield + tech_tree.scouting_bonus) {
+            commands.log_info(format!("Scout found loot: {} x{}", loot.loot_type.label(), loot.quantity));
+            inventory.add(loot.loot_type, loot.quantity);
         }
+        commands.play_sound(SoundEffect::Loot);
+
+        // Leaving Scouting removes the Scout marker
+        commands.entity(entity).set_bob_state(BobState::Returning);
     }
 }
 
 fn attacking_system(
-    mut attacker_query: Query<(Entity, Option<&mut Bob>, &mut Attack, &Transform)>, // Add Transform
-    mut health_query: Query<(&mut Health, &Transform)>, // Add Transform
+    mut attacker_query: Query<(Entity, Option<&Bob>, Has<Turret>, Has<Enemy>, Option<&Animator>, Option<&PendingHit>, &mut Attack, &Transform)>, // Add Transform
+    mut target_query: Query<DamageTarget>,
+    spatial: SpatialQuery,
+    sheets: Res<SpriteSheets>,
     time: Res<Time>,
     mut commands: Commands,
-    mut grid_state: ResMut<GridState>,
     mut next_state: ResMut<NextState<GameStates>>,
 ) {
-    // Handle all attacks (both Bobs and Enemies)
-    for (entity, maybe_bob, mut attack, attacker_transform) in attacker_query.iter_mut() {
+    // Handle all attacks (Bobs, Enemies and Turrets)
+    for (entity, maybe_bob, is_turret, is_enemy, maybe_animator, maybe_pending, mut attack, attacker_transform) in attacker_query.iter_mut() {
+        // Every attacker can be damaged, so its status effects are read through the target query.
+        // Stuns hold the attack, slows make the cooldown recover slower
+        let speed_multiplier = target_query
+            .get(entity)
+            .ok()
+            .and_then(|attacker| attacker.status.map(StatusEffects::speed_multiplier))
+            .unwrap_or(1.0);
+        if speed_multiplier <= 0.0 {
+            continue;
+        }
+
         if attack.current_cooldown <= 0.0 {
-            // Try to get the target's health and apply damage
-            if let Ok((mut health, target_transform)) = health_query.get_mut(attack.target_entity) {
-                health.take_damage(attack.damage);
-                
-                // Spawn floating damage text
-                spawn_floating_text(
-                    &mut commands,
-                    target_transform.translation,
-                    format!("-{}", attack.damage as i32),
-                    Color::srgb(1.0, 0.2, 0.2), // Red damage numbers
-                );
+            let attacker_pos = attacker_transform.translation.xy();
+            // The last swing never reached its hit frame (another clip cut it short), it lands now instead of getting lost
+            if let Some(pending) = maybe_pending {
+                if let Some(mut target) = target_query.get_mut(pending.target_entity).ok().filter(|target| !target.health.is_dead()) {
+                    land_hit(&mut commands, &mut next_state, entity, attacker_pos, !is_enemy, pending, &mut target);
+                }
+                commands.entity(entity).remove::<PendingHit>();
+            }
+
+            // Try to get the target's health and apply damage, dead targets count as gone
+            if let Some(mut target) = target_query.get_mut(attack.target_entity).ok().filter(|target| !target.health.is_dead()) {
+                // Hold fire until the hitboxes are within range, the cooldown stays ready meanwhile
+                if !spatial.in_range(entity, attack.target_entity, attack.range) {
+                    continue;
+                }
+                let hit = PendingHit {
+                    target_entity: attack.target_entity,
+                    damage: attack.damage,
+                    projectile_speed: attack.projectile_speed,
+                };
+                // Animated units strike on the hit frame of their Attack clip (on_attack_hit), turrets right away
+                if maybe_animator.is_some_and(|animator| sheets.has_event(animator.sheet(), animation::AnimationClip::Attack, "hit")) {
+                    commands.entity(entity).insert(hit);
+                } else {
+                    land_hit(&mut commands, &mut next_state, entity, attacker_pos, !is_enemy, &hit, &mut target);
+                }
                 
-                if let Some(_) = maybe_bob {
+                if maybe_bob.is_some() {
                     // Spawn attack message
-                    spawn_floating_text(
-                        &mut commands,
-                        attacker_transform.translation,
-                        "BOB ATTACK!".to_string(),
-                        Color::srgb(0.2, 0.8, 1.0), // Blue for Bob
+                    commands.floating_text(
+                        TextPopup::new("BOB ATTACK!", Color::srgb(0.2, 0.8, 1.0), attacker_transform.translation).over(entity), // Blue for Bob
+                    );
+                } else if is_turret {
+                    commands.floating_text(
+                        TextPopup::new("TURRET FIRE!", Color::srgb(0.6, 0.9, 0.6), attacker_transform.translation).over(entity), // Green for turrets
                     );
                 } else {
                     // Spawn enemy attack message
-                    spawn_floating_text(
-                        &mut commands,
-                        attacker_transform.translation,
-                        "ENEMY ATTACK!".to_string(),
-                        Color::srgb(1.0, 0.8, 0.2), // Orange for enemy
+                    commands.floating_text(
+                        TextPopup::new("ENEMY ATTACK!", Color::srgb(1.0, 0.8, 0.2), attacker_transform.translation).over(entity), // Orange for enemy
                     );
                 }
-                
-                if health.is_dead() {
-                    if maybe_bob.is_some() {
-                        // Spawn victory message
-                        spawn_floating_text(
-                            &mut commands,
-                            target_transform.translation,
-                            "VICTORY!".to_string(),
-                            Color::srgb(0.0, 1.0, 0.0), // Green for victory
-                        );
-                        next_state.set(GameStates::Win);
-                    } else {
-                        // Spawn defeat message
-                        spawn_floating_text(
-                            &mut commands,
-                            target_transform.translation,
-                            "BASE DESTROYED!".to_string(),
-                            Color::srgb(1.0, 0.0, 0.0), // Red for defeat
-                        );
-                        next_state.set(GameStates::Loss);
-                    }
-                    //commands.entity(attack.target_entity).despawn();
-                }
             } else {
                 // Target no longer exists, remove Attack component
                 commands.entity(entity).remove::<Attack>();
                 
-                // If this is a Bob, return it to grid
-                if let Some(mut bob) = maybe_bob {
-                    if let Some(new_grid_pos) = grid_state.find_first_available() {
-                        bob.grid_position = new_grid_pos;
-                        grid_state.occupy(new_grid_pos);
-                        bob.state = BobState::Idling;
+                // If this is a Bob, move on to the next enemy or return it to grid when there is none left.
+                // Dropping AtTarget makes bob_system walk it over and on_bob_arrived start the next attack
+                if maybe_bob.is_some() {
+                    let enemy_left = target_query
+                        .iter()
+                        .any(|target| target.is_enemy && target.entity != attack.target_entity && !target.health.is_dead());
+                    if enemy_left {
+                        commands.entity(entity).remove::<AtTarget>();
                     } else {
-                        // Just replace println with floating text - keep original logic
-                        spawn_floating_text(
-                            &mut commands,
-                            attacker_transform.translation,
-                            "NO GRID SPACE!".to_string(),
-                            Color::srgb(1.0, 1.0, 0.0),
-                        );
+                        commands.entity(entity).set_bob_state(BobState::Returning);
                     }
                 }
                 continue;
             }
             attack.current_cooldown = attack.max_cooldown;
         } else {
-            attack.current_cooldown -= time.delta_secs();
+            attack.current_cooldown -= time.delta_secs() * speed_multiplier;
         }
     }
 }
 
-fn floating_text_system(
-    mut query: Query<(Entity, &mut Transform, &mut FloatingText)>,
+// Melee hits deal their damage right away, ranged ones fire a projectile that deals it on impact
+fn land_hit(
+    commands: &mut Commands,
+    next_state: &mut NextState<GameStates>,
+    attacker: Entity,
+    attacker_pos: Vec2,
+    friendly: bool,
+    hit: &PendingHit,
+    target: &mut DamageTargetItem,
+) {
+    match hit.projectile_speed {
+        Some(speed) => spawn_projectile(commands, attacker, attacker_pos, target.transform.translation.xy(), speed, hit.damage, friendly),
+        None => deal_damage(commands, next_state, target, hit.damage, attacker),
+    }
+}
+
+fn on_attack_hit(
+    trigger: On<AnimationEvent>,
+    attacker_query: Query<(&PendingHit, &Transform, Has<Enemy>)>,
+    mut target_query: Query<DamageTarget>,
+    mut next_state: ResMut<NextState<GameStates>>,
     mut commands: Commands,
-    time: Res<Time>,
 ) {
-    for (entity, mut transform, mut floating_text) in query.iter_mut() {
-        // Move the text upward
-        transform.translation += floating_text.velocity * time.delta_secs();
-        
-        // Fade out over time
-        floating_text.timer.tick(time.delta());
-        
-        if floating_text.timer.finished() {
-            commands.entity(entity).despawn();
+    if trigger.name != "hit" {
+        return;
+    }
+    let Ok((pending, transform, is_enemy)) = attacker_query.get(trigger.entity) else {
+        return;
+    };
+    commands.entity(trigger.entity).remove::<PendingHit>();
+    // The target may have died or gone while the attacker was winding up
+    let Some(mut target) = target_query.get_mut(pending.target_entity).ok().filter(|target| !target.health.is_dead()) else {
+        return;
+    };
+    land_hit(&mut commands, &mut next_state, trigger.entity, transform.translation.xy(), !is_enemy, pending, &mut target);
+}
+
+// Everything needed to damage something, used by melee attacks and projectiles
+#[derive(QueryData)]
+#[query_data(mutable)]
+struct DamageTarget {
+    entity: Entity,
+    health: &'static mut Health,
+    transform: &'static Transform,
+    armour: Option<&'static Armour>,
+    resistances: Option<&'static Resistances>,
+    status: Option<&'static mut StatusEffects>,
+    is_enemy: Has<Enemy>,
+    is_boss: Has<Boss>,
+    is_base: Has<HomeBase>,
+}
+
+// Rolls the hit against armour and resistances, reports it through DamageEvent and ends the game when the enemy or the home base falls
+fn deal_damage(
+    commands: &mut Commands,
+    next_state: &mut NextState<GameStates>,
+    target: &mut DamageTargetItem,
+    damage: Damage,
+    source: Entity,
+) {
+    let (rolled, crit) = damage.roll(target.armour, target.resistances);
+    let amount = match target.status.as_mut() {
+        Some(effects) => effects.absorb(rolled),
+        None => rolled,
+    };
+    target.health.take_damage(amount);
+
+    // on_damage shows the floating damage number
+    commands.trigger(DamageEvent {
+        entity: target.entity,
+        source,
+        kind: damage.kind,
+        amount,
+        absorbed: rolled - amount,
+        crit,
+        resisted: is_resisted(damage.kind, target.resistances),
+    });
+    if let Some(effect) = damage.status {
+        commands.trigger(ApplyStatusEvent { entity: target.entity, effect: effect.with_source(source) });
+    }
+
+    if target.health.is_dead() {
+        if target.is_boss {
+            // Spawn victory message
+            commands.floating_text(
+                TextPopup::new("VICTORY!", Color::srgb(0.0, 1.0, 0.0), target.transform.translation) // Green for victory
+                    .with_style(FloatingTextStyle::ANNOUNCEMENT),
+            );
+            next_state.set(GameStates::Win);
+        } else if target.is_base {
+            // Spawn defeat message
+            commands.floating_text(
+                TextPopup::new("BASE DESTROYED!", Color::srgb(1.0, 0.0, 0.0), target.transform.translation) // Red for defeat
+                    .with_style(FloatingTextStyle::ANNOUNCEMENT),
+            );
+            next_state.set(GameStates::Loss);
         }
     }
 }
 
 fn bob_system(
-    mut query: Query<(Entity, &Bob, &Transform, Option<&mut Movement>, Option<&Scout>, Option<&Attack>), With<Head>>,
-    enemy_query: Query<(Entity, &Transform, &Size, &Enemy, &Health)>,
+    mut query: Query<(
+        Entity,
+        CurrentBobState,
+        &ArmsKind,
+        &Transform,
+        &Size,
+        Option<&ScoutSite>,
+        Option<&MoveOrder>,
+        Option<&AttackOrder>,
+        Option<&mut Movement>,
+        Option<&AtTarget>,
+    ), With<Bob>>,
+    enemy_query: Query<(Entity, &Transform, &Size, &Health), With<Enemy>>,
+    home_base_query: Query<(&Transform, &Size), With<HomeBase>>,
+    formation: Res<Formation>,
     mut commands: Commands,
 ) {
-
-    // Position for scouting
-    let scout_target = Vec2::new(0.0, -400.0); 
-
-    for (entity, bob,  transform, maybe_movement, maybe_scout, maybe_attack) in query.iter_mut() {
-        match bob.state {
+    for (entity, state, arms, transform, bob_size, maybe_site, maybe_move_order, maybe_attack_order, maybe_movement, maybe_at_target) in query.iter_mut() {
+        let Some(state) = state.get() else {
+            continue;
+        };
+        let target = match state {
             BobState::Attacking => {
-                // Attack logic here - check if it has Movement component for attack behavior
-
-                // query for any entity with enemy component
-                let enemy_data = if let Some(data) = enemy_query.iter().next() {
-                    data
-                } else {
-                    // No enemy found, exit early without doing anything
-                    return;
+                // go for the ordered enemy, otherwise the closest one
+                let Some((_, enemy_transform, enemy_size)) = bob_attack_target(transform.translation.xy(), maybe_attack_order, &enemy_query) else {
+                    // Nothing left to fight, head back to the formation
+                    commands.entity(entity).set_bob_state(BobState::Returning);
+                    continue;
                 };
-                
-                let (enemy_entity, enemy_transform, enemy_size, _enemy, enemy_health) = enemy_data;
-                
-                // Calculate attack position just below the enemy sprite
-                let enemy_pos = enemy_transform.translation.xy();
-                let attack_target_pos = Vec2::new(
-                    enemy_pos.x,
-                    enemy_pos.y - (enemy_size.0.y / 2.0) // Just below the bottom edge
-                );
-
-                // is it in distance of attack target?
-                let distance_to_target = transform.translation.xy().distance(attack_target_pos);
-                if distance_to_target > 2.0 {
-                    // not in range yet, make it move towards the target
-                    if let Some(mut movement) = maybe_movement {
-                        // Update existing movement target
-                        movement.target = attack_target_pos;
-                    } else {
-                        // No movement component yet, insert one
-                        commands.entity(entity).insert(Movement {
-                            speed: 100.0,
-                            target: attack_target_pos,
-                        });
-                    }
-                } else {
-                    if enemy_health.is_dead() == false {
-                        if maybe_attack.is_none() {
-                            commands.entity(entity).insert(Attack {
-                                target_entity: enemy_entity,
-                                damage: 10.0,
-                                max_cooldown: 1.0,
-                                current_cooldown: 0.0,  // Start at 0 to attack immediately
-                            });
-                        }
-                    }
-                }                
+                bob_attack_position(enemy_transform, enemy_size, bob_size, arms.standoff())
             },
             BobState::Idling => {
-                let grid_pos = calculate_grid_position(bob.grid_position);
-                let distance_to_target = transform.translation.xy().distance(grid_pos);
-                if distance_to_target > 2.0 {
-                    // not in range yet, make it move towards the target
-                    if let Some(mut movement) = maybe_movement {
-                        // Update existing movement target
-                        movement.target = grid_pos;
-                    } else {
-                        // No movement component yet, insert one
-                        commands.entity(entity).insert(Movement {
-                            speed: 100.0,
-                            target: grid_pos,
-                        });
-                    }
-                } else {
-                    // in range, remove Movement component to stop moving 
-                    if maybe_movement.is_some() {
-                        commands.entity(entity).remove::<Movement>();
-                    } 
+                let Some(slot) = formation.slot_of(entity) else {
+                    continue;
+                };
+                formation.slot_position(slot)
+            },
+            BobState::Scouting => {
+                // on_scout hands out the site together with the state
+                let Some(site) = maybe_site else {
+                    continue;
+                };
+                site.0
+            },
+            BobState::Returning => {
+                // Back at the formation, take the next free slot
+                if maybe_at_target.is_some() {
+                    commands.entity(entity).set_bob_state(BobState::Idling);
+                    continue;
                 }
+                formation.slot_position(formation.len())
             },
-            BobState::Scouting => {                
-
-                // is it in distance of scouting target?
-                let distance_to_target = transform.translation.xy().distance(scout_target);
-                if distance_to_target > 2.0 {
-                    // not in range yet, make it move towards the target
-                    if let Some(mut movement) = maybe_movement {
-                        // Update existing movement target
-                        movement.target = scout_target;
-                    } else {
-                        // No movement component yet, insert one
-                        commands.entity(entity).insert(Movement {
-                            speed: 100.0,
-                            target: scout_target,
-                        });
-                    }
-                } else {
-                    // in range, remove Movement component to stop moving 
-                    if maybe_movement.is_some() {
-                        commands.entity(entity).remove::<Movement>();
-                    } 
+            BobState::Repairing => {
+                let Some((home_base_transform, home_base_size)) = home_base_query.iter().next() else {
+                    continue;
+                };
+                // Repair bay just below the home base, touching its bottom edge
+                home_base_transform.translation.xy() - Vec2::new(0.0, (home_base_size.0.y + bob_size.0.y) / 2.0)
+            },
+            BobState::Moving => {
+                let Some(order) = maybe_move_order else {
+                    continue;
+                };
+                order.0
+            },
+            BobState::Dead => continue,
+        };
 
-                    if maybe_scout.is_none() {
-                        commands.entity(entity).insert(Scout);
-                    }
-                }
-            }
-        }
+        move_towards(&mut commands, entity, maybe_movement, maybe_at_target, Movement::new(100.0, target));
     }
 }
 
+// The living enemy closest to `position`
+fn nearest_enemy<'a>(
+    position: Vec2,
+    enemies: impl Iterator<Item = (Entity, &'a Transform, &'a Size, &'a Health)>,
+) -> Option<(Entity, &'a Transform, &'a Size)> {
+    enemies
+        .filter(|(_, _, _, health)| !health.is_dead())
+        .min_by(|a, b| {
+            let distance_a = a.1.translation.xy().distance(position);
+            let distance_b = b.1.translation.xy().distance(position);
+            distance_a.total_cmp(&distance_b)
+        })
+        .map(|(entity, transform, size, _)| (entity, transform, size))
+}
+
+// The enemy a Bob was ordered to attack while it is still alive, the closest one otherwise
+fn bob_attack_target<'a>(
+    position: Vec2,
+    maybe_order: Option<&AttackOrder>,
+    enemy_query: &'a Query<(Entity, &Transform, &Size, &Health), With<Enemy>>,
+) -> Option<(Entity, &'a Transform, &'a Size)> {
+    maybe_order
+        .and_then(|order| enemy_query.get(order.0).ok())
+        .filter(|(_, _, _, health)| !health.is_dead())
+        .map(|(entity, transform, size, _)| (entity, transform, size))
+        .or_else(|| nearest_enemy(position, enemy_query.iter()))
+}
+
+// Position below the enemy sprite, the Bob's hitbox `standoff` pixels away from its bottom edge
+fn bob_attack_position(enemy_transform: &Transform, enemy_size: &Size, bob_size: &Size, standoff: f32) -> Vec2 {
+    let enemy_pos = enemy_transform.translation.xy();
+    Vec2::new(
+        enemy_pos.x,
+        enemy_pos.y - (enemy_size.0.y + bob_size.0.y) / 2.0 - standoff // Just below the bottom edge
+    )
+}
 
-fn enemy_system(
-    mut enemy_query: Query<(Entity, &Transform, Option<&mut Movement>, Option<&Attack>), With<Enemy>>,
-    home_base_query: Query<(Entity, &Transform, &Size), With<HomeBase>>,
+// Bobs start scouting or attacking once they reach the spot bob_system sent them to
+fn on_bob_arrived(
+    trigger: On<ArrivedAtTarget>,
+    bob_query: Query<(CurrentBobState, &Transform, &ArmsKind, Option<&Scout>, Option<&Attack>, Option<&AttackOrder>), With<Bob>>,
+    enemy_query: Query<(Entity, &Transform, &Size, &Health), With<Enemy>>,
     mut commands: Commands,
 ) {
-    // Get home base data
-    let home_base_data = if let Some(data) = home_base_query.iter().next() {
-        data
-    } else {
-        // No home base found, exit early
+    let Ok((state, transform, arms, maybe_scout, maybe_attack, maybe_order)) = bob_query.get(trigger.entity) else {
         return;
     };
-    
-    let (home_base_entity, home_base_transform, home_base_size) = home_base_data;
-    
-    // Calculate attack position (above the home base)
-    let home_base_pos = home_base_transform.translation.xy();
-    let attack_target_pos = Vec2::new(
-        home_base_pos.x,
-        home_base_pos.y + (home_base_size.0.y / 2.0) // Just above the top edge
-    );
-    
-    for (entity, transform, maybe_movement, maybe_attack) in enemy_query.iter_mut() {
-        let distance_to_target = transform.translation.xy().distance(attack_target_pos);            
-        
-        if distance_to_target > 2.0 {
-            // Not in range yet, make it move towards the target
-            if let Some(mut movement) = maybe_movement {
-                // Update existing movement target
-                movement.target = attack_target_pos;
-            } else {
-                // No movement component yet, insert one
-                println!("Inserting Movement component for enemy {:?}", entity);
-                commands.entity(entity).insert(Movement {
-                    speed: 10.0,
-                    target: attack_target_pos,
-                });
-            }
-        } else {
-            // In range, remove Movement component to stop moving
-            if maybe_movement.is_some() {
-                commands.entity(entity).remove::<Movement>();
-            }
-            
-            // Start attacking if not already attacking
+
+    match state.get() {
+        Some(BobState::Attacking) => {
+            let Some((enemy_entity, _, _)) = bob_attack_target(transform.translation.xy(), maybe_order, &enemy_query) else {
+                return;
+            };
             if maybe_attack.is_none() {
-                println!("Enemy {:?} reached home base!", entity);
-                commands.entity(entity).insert(Attack {
-                    target_entity: home_base_entity,
-                    damage: 50.0,
-                    max_cooldown: 5.0,
-                    current_cooldown: 0.0,  // Start at 0 to attack immediately
-                });
+                commands.entity(trigger.entity).insert(arms.attack(enemy_entity));
             }
-        }
+        },
+        Some(BobState::Scouting) => {
+            if maybe_scout.is_none() {
+                commands.entity(trigger.entity).insert(Scout);
+            }
+        },
+        Some(BobState::Idling | BobState::Returning | BobState::Repairing | BobState::Moving | BobState::Dead) | None => {},
     }
 }
 
-
 fn on_attack(
     _trigger: On<StartAttackingEvent>,
-    mut query: Query<(Entity, &mut Bob)>,
-    mut grid_state: ResMut<GridState>,
+    query: Query<Entity, With<Idling>>,
+    enemy_query: Query<&Health, With<Enemy>>,
+    mut commands: Commands,
 ) {
-    // Find the first idle bob and change its state directly
-    if let Some((entity, mut bob)) = query.iter_mut().find(|(_, bob)| matches!(bob.state, BobState::Idling)) {
-        bob.state = BobState::Attacking;
-        grid_state.free(bob.grid_position);  // Free up this Bob's grid position
-        println!("Sent head {:?} on attacking mission!", entity);
+    if enemy_query.iter().all(|health| health.is_dead()) {
+        commands.log_warning("There are no enemies to attack!");
+        return;
+    }
+    // Find the first idle bob, leaving Idling frees up its grid position
+    if let Some(entity) = query.iter().next() {
+        commands.entity(entity).set_bob_state(BobState::Attacking);
+        commands.log_info("Sent a Bob on an attacking mission!");
     } else {
-        println!("There are no idle bobs available!");
+        commands.log_warning("There are no idle bobs available!");
     }
 }
 
 fn on_scout(
     _trigger: On<StartScoutingEvent>,
-    mut query: Query<(Entity, &mut Bob)>,
-    mut grid_state: ResMut<GridState>,
+    query: Query<Entity, With<Idling>>,
+    map: Res<BattleMap>,
+    mut commands: Commands,
 ) {
-    // Find the first idle bob and change its state directly
-    if let Some((entity, mut bob)) = query.iter_mut().find(|(_, bob)| matches!(bob.state, BobState::Idling)) {
-        bob.state = BobState::Scouting;
-        grid_state.free(bob.grid_position);  // Free up this Bob's grid position
-        println!("Sent head {:?} on scouting mission!", entity);
+    // Find the first idle bob, leaving Idling frees up its grid position
+    if let Some(entity) = query.iter().next() {
+        commands.entity(entity).set_bob_state(BobState::Scouting).insert(ScoutSite(map.random_scout_site()));
+        commands.log_info("Sent a Bob on a scouting mission!");
     } else { 
-        println!("There are no idle bobs available!"); 
+        commands.log_warning("There are no idle bobs available!"); 
     }
 }
 
@@ -767,106 +1181,62 @@ fn on_build_bob (
     _trigger: On<BuildBobEvent>,
     mut commands: Commands,
     mut query: Query<&mut ComponentsInventory>, //can be changed to inventory later?
-    mut grid_state: ResMut<GridState>,
+    formation: Res<Formation>,
     slot_query: Query<&SlotFilled, Or<(With<HeadSlot>, With<BodySlot>, With<LeftArmSlot>, With<RightArmSlot>, With<LeftLegSlot>, With<RightLegSlot>)>>,
-    asset_server: Res<AssetServer>
+    arms_query: Query<&ArmsToggle>,
 ) {
     if let Ok(mut inventory) = query.single_mut() {
-        println!("Found {} heads in inventory", inventory.count);
+        debug!("Found {} heads in inventory", inventory.heads);
 
          // Check if all 6 slots are filled
         let filled_slots = slot_query.iter().filter(|slot| slot.0).count();
         let total_slots = slot_query.iter().count();
         
-        println!("Filled slots: {}/{}", filled_slots, total_slots);
+        debug!("Filled slots: {}/{}", filled_slots, total_slots);
         
         if filled_slots < total_slots {
-            println!("Cannot build Bob! Not all body parts are placed. Need {}/{} slots filled.", filled_slots, total_slots);
+            commands.log_warning(format!("Cannot build Bob! Not all body parts are placed ({}/{} slots filled).", filled_slots, total_slots));
             return; // Exit early if not all slots are filled
         }
         
         if filled_slots == total_slots {
-            // Find the first available grid position
-            if let Some(grid_position) = grid_state.find_first_available() {
-                // Deduct one head from inventory
-                // inventory.count -= 1;
-                println!("built 1 bob, {} remaining parts:", inventory.count);
-                
-                let grid_pos = calculate_grid_position(grid_position);
-                grid_state.occupy(grid_position);  // Mark this position as occupied
-
-                let bob_size = Size::square(100.0);
-                let bob_size_vec = bob_size.0;  // Extract Vec2 before moving
-                commands.spawn((
-                    Bob { 
-                        state: BobState::Idling,
-                        grid_position,
-                    },
-                    Head,
-                    Health::new(50.0),
-                    bob_size,
-                    Sprite {
-                        image: asset_server.load("sprites/BoB.png"),
-                        custom_size: Some(bob_size_vec),
-                        ..default()
-                    },
-                    Transform::from_xyz(grid_pos.x, grid_pos.y, 2.),
-                    Name::new("Bob"),
-                ));//.observe(update_selected_on);
-                commands.trigger(ResetBuilderUIEvent); //reset builder
-            } else {
-                println!("Grid is full! Cannot spawn more Bobs.");
-            }
+            // Deduct one head from inventory
+            // inventory.count -= 1;
+            commands.log_info(format!("Built a Bob, {} parts remaining", inventory.count()));
+
+            // New Bobs start in the next free slot at the back of the formation
+            let grid_pos = formation.slot_position(formation.len());
+
+            let arms = arms_query.single().map_or(ArmsKind::default(), |toggle| toggle.0);
+            commands.spawn((
+                Bob,
+                Idling,  // joins the formation through the enter hook
+                arms,
+                arms.defences(),
+                Health::new(50.0),
+                Size::square(100.0),
+                Collider::pushable().with_shape(ColliderShape::Circle),
+                Transform::from_xyz(grid_pos.x, grid_pos.y, 2.),
+                Visibility::default(),
+                Animator::new("sprites/BoB.png"),
+                Name::new("Bob"),
+            )).with_children(|parent| spawn_bob_parts(parent, arms))
+            .observe(on_bob_clicked);
+            commands.play_sound(SoundEffect::BuildBob);
+            commands.trigger(ResetBuilderUIEvent); //reset builder
         } else {
-            println!("No robot components available in inventory!");
+            commands.log_warning("No robot components available in inventory!");
         }
     } else {
-        println!("No inventory found!");
-    }
-}
-
-/*fn update_selected_on(trigger: On<Pointer<Press>>, mut commands: Commands, mut query: Query<Entity, (With<Head>, With<Selected>)>)
-{
-    let clicked_entity =  query.get_mut(trigger.event_target()).unwrap(); //Should maybe not use unwrap here?
-    println!("Head clicked: {:?}", clicked_entity);
-
-    // Remove Selected from all other heads
-    for selected_entity in query.iter() {
-        if selected_entity != clicked_entity {
-            commands.entity(selected_entity).remove::<Selected>();
-            println!("Removed selection from entity: {:?}", selected_entity);
-        }
+        commands.log_error("No inventory found!");
     }
-
-    // Add Selected to clicked entity
-    commands.entity(clicked_entity).insert(Selected);
-    println!("Selected head entity: {:?}", clicked_entity);
-
-}*/
-
-
-fn calculate_grid_position(position: usize) -> Vec2 {
-    const GRID_COLS: usize = 3;
-    const SLOT_SIZE: f32 = 25.0; //one bob should fit in here
-    const PADDING: f32 = 10.0; //padding between bobs
-    const TOTAL_SPACING: f32 = SLOT_SIZE + PADDING; 
-
-    const GRID_START_X: f32 = - 250.0; // start grid a bit to the left, with 3 items per row one row width should be 180 px and build button is 65 px wide =~ 250
-    const GRID_START_Y: f32 = -75.0;
-
-    let col = position % GRID_COLS;
-    let row = position / GRID_COLS;
-
-    let x = GRID_START_X + (col as f32 * TOTAL_SPACING);
-    let y = GRID_START_Y + (row as f32 * TOTAL_SPACING);
-
-    Vec2::new(x,y)
 }
 
 fn restart_game() {
-    println!("Restarting application...");
+    info!("Restarting application...");
     // Get the current executable path and restart
     let current_exe = std::env::current_exe().unwrap();
-    process::Command::new(current_exe).spawn().unwrap();
+    // Pass the original arguments along so the selected difficulty carries over
+    process::Command::new(current_exe).args(std::env::args().skip(1)).spawn().unwrap();
     process::exit(0);
 }
\ No newline at end of file
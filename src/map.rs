@@ -0,0 +1,178 @@
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use crate::collision::Collider;
+use crate::navigation::Obstacle;
+use crate::message_log::GameLogCommands;
+use crate::Size;
+
+const MAP_SIZE: Vec2 = Vec2::new(1800.0, 1200.0); // the camera stays inside this area
+const HOME_BASE_SIZE: Vec2 = Vec2::new(300.0, 300.0);
+const FORMATION_OFFSET: Vec2 = Vec2::new(-540.0, 50.0); // top left formation slot, relative to the base
+const BOSS_DISTANCE: f32 = 270.0; // boss spawn above the base centre
+const SPAWN_BAND: (f32, f32) = (400.0, 450.0); // enemies come in just off the top of the screen
+const SCOUT_BAND: (f32, f32) = (-450.0, -350.0); // scouting sites lie south of the base
+const FIELD_HALF_WIDTH: f32 = 600.0;
+const OBSTACLE_CLEARANCE: f32 = 60.0; // free space kept around the base, spawns, sites and other heaps
+const PLACEMENT_ATTEMPTS: usize = 30;
+const SCATTER_TINT: Color = Color::srgb(0.55, 0.5, 0.45); // decoration is darker so it reads as background
+
+/// A garbage heap units have to walk around
+#[derive(Debug, Clone)]
+pub struct MapObstacle {
+    pub position: Vec2,
+    pub size: Vec2,
+}
+
+/// Purely decorative garbage, drawn behind the units
+#[derive(Debug, Clone)]
+pub struct Scatter {
+    pub position: Vec2,
+    pub size: f32,
+    pub flip: bool,
+}
+
+/// Battlefield layout generated from a seed, every system that needs a map position reads it from here
+#[derive(Resource, Debug, Clone)]
+pub struct BattleMap {
+    pub seed: u64,
+    pub bounds: Rect,
+    pub base: Vec2,
+    pub formation_origin: Vec2, // centre of the top left formation slot
+    pub boss_spawn: Vec2,
+    pub enemy_spawns: Vec<Vec2>,
+    pub scout_sites: Vec<Vec2>,
+    pub obstacles: Vec<MapObstacle>,
+    pub scatter: Vec<Scatter>,
+}
+
+// Where a Bob on a scouting mission is headed
+#[derive(Component)]
+pub struct ScoutSite(pub Vec2);
+
+impl BattleMap {
+    /// Reads the seed from command line arguments, e.g. `--seed=1234`, a random one is picked otherwise
+    pub fn from_args(args: impl Iterator<Item = String>, warnings: &mut Vec<String>) -> Self {
+        let seed = args
+            .filter_map(|arg| arg.strip_prefix("--seed=").map(str::to_string))
+            .next()
+            .and_then(|value| {
+                value.parse().map_err(|_| warnings.push(format!("Could not parse seed '{}', picking a random one", value))).ok()
+            })
+            .unwrap_or_else(|| rand::thread_rng().r#gen());
+        Self::generate(seed)
+    }
+
+    pub fn generate(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let base = Vec2::new(rng.gen_range(-150.0..150.0), rng.gen_range(0.0..80.0));
+        let formation_origin = base + FORMATION_OFFSET;
+        let boss_spawn = Vec2::new(rng.gen_range(-200.0..200.0), base.y + BOSS_DISTANCE);
+
+        // One point per lane so spawns and sites spread across the field instead of bunching up
+        let spawn_count = rng.gen_range(3..=4);
+        let enemy_spawns = lanes(&mut rng, spawn_count, SPAWN_BAND);
+        let site_count = rng.gen_range(2..=4);
+        let scout_sites = lanes(&mut rng, site_count, SCOUT_BAND);
+
+        // Keep-out areas: the base with its turrets and repair bay, the formation, the boss and the spots units walk to
+        let formation_area = Rect::from_corners(formation_origin - Vec2::new(60.0, -60.0), formation_origin + Vec2::new(300.0, -460.0));
+        let mut taken: Vec<Rect> = vec![Rect::from_center_size(base, HOME_BASE_SIZE + Vec2::splat(200.0)), formation_area, Rect::from_center_size(boss_spawn, Vec2::splat(200.0))];
+        taken.extend(enemy_spawns.iter().chain(scout_sites.iter()).map(|point| Rect::from_center_size(*point, Vec2::splat(80.0))));
+
+        let mut obstacles = Vec::new();
+        for _ in 0..rng.gen_range(2..=4) {
+            let size = Vec2::splat(rng.gen_range(100.0..180.0));
+            let Some(position) = place(&mut rng, size, &taken, (SCOUT_BAND.1 + 50.0, SPAWN_BAND.0 - 100.0)) else {
+                continue;
+            };
+            taken.push(Rect::from_center_size(position, size));
+            obstacles.push(MapObstacle { position, size });
+        }
+
+        // Decoration may overlap each other, just not the things that matter
+        let mut scatter = Vec::new();
+        for _ in 0..rng.gen_range(6..=10) {
+            let size = rng.gen_range(40.0..80.0);
+            if let Some(position) = place(&mut rng, Vec2::splat(size), &taken, (SCOUT_BAND.0, SPAWN_BAND.1)) {
+                scatter.push(Scatter { position, size, flip: rng.gen_bool(0.5) });
+            }
+        }
+
+        let bounds = Rect::from_center_size(Vec2::ZERO, MAP_SIZE);
+        Self { seed, bounds, base, formation_origin, boss_spawn, enemy_spawns, scout_sites, obstacles, scatter }
+    }
+
+    pub fn random_spawn(&self) -> Vec2 {
+        self.enemy_spawns.choose(&mut rand::thread_rng()).copied().unwrap_or(self.boss_spawn)
+    }
+
+    pub fn nearest_spawn(&self, position: Vec2) -> Vec2 {
+        self.enemy_spawns
+            .iter()
+            .copied()
+            .min_by(|a, b| a.distance(position).total_cmp(&b.distance(position)))
+            .unwrap_or(self.boss_spawn)
+    }
+
+    pub fn random_scout_site(&self) -> Vec2 {
+        self.scout_sites.choose(&mut rand::thread_rng()).copied().unwrap_or(self.base)
+    }
+}
+
+// `count` points spread over equally wide lanes across the field, each at a random spot in its lane
+fn lanes(rng: &mut StdRng, count: usize, (min_y, max_y): (f32, f32)) -> Vec<Vec2> {
+    let lane_width = FIELD_HALF_WIDTH * 2.0 / count as f32;
+    (0..count)
+        .map(|lane| {
+            let left = -FIELD_HALF_WIDTH + lane as f32 * lane_width;
+            Vec2::new(rng.gen_range(left + lane_width * 0.2..left + lane_width * 0.8), rng.gen_range(min_y..max_y))
+        })
+        .collect()
+}
+
+// A random spot between `min_y` and `max_y` whose rect stays clear of everything taken, None if there is no room
+fn place(rng: &mut StdRng, size: Vec2, taken: &[Rect], (min_y, max_y): (f32, f32)) -> Option<Vec2> {
+    (0..PLACEMENT_ATTEMPTS)
+        .map(|_| Vec2::new(rng.gen_range(-FIELD_HALF_WIDTH..FIELD_HALF_WIDTH), rng.gen_range(min_y..max_y)))
+        .find(|position| {
+            let rect = Rect::from_center_size(*position, size + Vec2::splat(OBSTACLE_CLEARANCE));
+            taken.iter().all(|other| rect.intersect(*other).is_empty())
+        })
+}
+
+pub fn setup_map(map: Res<BattleMap>, asset_server: Res<AssetServer>, mut commands: Commands) {
+    commands.log_info(format!("Battlefield seed {}", map.seed));
+
+    for obstacle in map.obstacles.iter() {
+        let size = Size::new(obstacle.size.x, obstacle.size.y);
+        commands.spawn((
+            size,
+            Sprite {
+                image: asset_server.load("sprites/Garbage_heap.png"),
+                custom_size: Some(obstacle.size),
+                ..default()
+            },
+            Transform::from_xyz(obstacle.position.x, obstacle.position.y, 1.0),
+            Name::new("Garbage Heap"),
+            Obstacle,
+            Collider::solid(),
+        ));
+    }
+
+    for scatter in map.scatter.iter() {
+        commands.spawn((
+            Sprite {
+                image: asset_server.load("sprites/Garbage_heap.png"),
+                custom_size: Some(Vec2::splat(scatter.size)),
+                color: SCATTER_TINT,
+                flip_x: scatter.flip,
+                ..default()
+            },
+            Transform::from_xyz(scatter.position.x, scatter.position.y, 0.5), // above the background, below the units
+            Name::new("Scrap"),
+        ));
+    }
+}
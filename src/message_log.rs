@@ -0,0 +1,107 @@
+use bevy::prelude::*;
+use std::collections::VecDeque;
+use crate::MatchClock;
+
+const MAX_LOG_ENTRIES: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn color(&self) -> Color {
+        match self {
+            Severity::Info => Color::srgb(0.85, 0.85, 0.85),
+            Severity::Warning => Color::srgb(1.0, 0.8, 0.2),
+            Severity::Error => Color::srgb(1.0, 0.3, 0.3),
+        }
+    }
+}
+
+/// A player facing message, trigger it through `GameLogCommands` instead of printing to the console
+#[derive(Event, Clone)]
+pub struct GameLog {
+    pub severity: Severity,
+    pub text: String,
+}
+
+pub struct LogEntry {
+    pub severity: Severity,
+    pub text: String,
+    pub time: f32, // match time the message was logged at
+}
+
+/// The most recent messages, newest first
+#[derive(Resource, Default)]
+pub struct MessageLog {
+    entries: VecDeque<LogEntry>,
+}
+
+impl MessageLog {
+    pub fn entries(&self) -> impl Iterator<Item = &LogEntry> {
+        self.entries.iter()
+    }
+
+    fn push(&mut self, entry: LogEntry) {
+        self.entries.push_front(entry);
+        self.entries.truncate(MAX_LOG_ENTRIES);
+    }
+}
+
+pub trait GameLogCommands {
+    fn log_message(&mut self, severity: Severity, text: impl Into<String>);
+
+    fn log_info(&mut self, text: impl Into<String>) {
+        self.log_message(Severity::Info, text);
+    }
+
+    fn log_warning(&mut self, text: impl Into<String>) {
+        self.log_message(Severity::Warning, text);
+    }
+
+    fn log_error(&mut self, text: impl Into<String>) {
+        self.log_message(Severity::Error, text);
+    }
+}
+
+impl GameLogCommands for Commands<'_, '_> {
+    fn log_message(&mut self, severity: Severity, text: impl Into<String>) {
+        self.trigger(GameLog {
+            severity,
+            text: text.into(),
+        });
+    }
+}
+
+/// Problems found before the app was running, e.g. command line flags that could not be parsed.
+/// `report_startup_warnings` moves them into the message log once the UI is up
+#[derive(Resource, Default)]
+pub struct StartupWarnings(pub Vec<String>);
+
+pub fn report_startup_warnings(warnings: Res<StartupWarnings>, mut commands: Commands) {
+    for warning in warnings.0.iter() {
+        commands.log_warning(warning.clone());
+    }
+}
+
+// Every message also goes to the regular bevy log so it still shows up in the console
+pub fn on_game_log(
+    trigger: On<GameLog>,
+    mut message_log: ResMut<MessageLog>,
+    match_clock: Res<MatchClock>,
+) {
+    match trigger.severity {
+        Severity::Info => info!("{}", trigger.text),
+        Severity::Warning => warn!("{}", trigger.text),
+        Severity::Error => error!("{}", trigger.text),
+    }
+
+    message_log.push(LogEntry {
+        severity: trigger.severity,
+        text: trigger.text.clone(),
+        time: match_clock.elapsed,
+    });
+}
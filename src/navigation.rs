@@ -0,0 +1,400 @@
+use bevy::prelude::*;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use crate::{Movement, Size};
+
+const CELL_SIZE: f32 = 25.0;
+const NAV_BOUNDS_MIN: Vec2 = Vec2::new(-1000.0, -700.0);
+const NAV_BOUNDS_MAX: Vec2 = Vec2::new(1000.0, 700.0);
+const OBSTACLE_CLEARANCE: f32 = 30.0; // keep unit centres this far away from obstacle edges
+const WAYPOINT_REACHED: f32 = 5.0;
+
+/// Marks a static entity (with Size and Transform) that units have to walk around
+#[derive(Component)]
+pub struct Obstacle;
+
+/// Waypoints towards the current `Movement::target`, filled in by `path_planning_system`
+#[derive(Component)]
+pub struct NavPath {
+    waypoints: Vec<Vec2>,
+    goal: Vec2, // the Movement target this path was planned for
+}
+
+impl NavPath {
+    pub fn next_waypoint(&self) -> Option<Vec2> {
+        self.waypoints.first().copied()
+    }
+
+    // Drop the current waypoint once it has been reached
+    pub fn advance(&mut self, position: Vec2) {
+        if let Some(waypoint) = self.next_waypoint()
+            && position.distance(waypoint) <= WAYPOINT_REACHED
+        {
+            self.waypoints.remove(0);
+        }
+    }
+}
+
+/// Walkability grid covering the battlefield, rebuilt whenever obstacles change
+#[derive(Resource)]
+pub struct NavGrid {
+    origin: Vec2,
+    cell_size: f32,
+    width: i32,
+    height: i32,
+    blocked: Vec<bool>,
+}
+
+impl Default for NavGrid {
+    fn default() -> Self {
+        Self::new(NAV_BOUNDS_MIN, NAV_BOUNDS_MAX, CELL_SIZE)
+    }
+}
+
+#[derive(PartialEq)]
+struct OpenNode {
+    cost: f32,
+    cell: IVec2,
+}
+
+impl Eq for OpenNode {}
+
+impl Ord for OpenNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed so BinaryHeap pops the cheapest node first
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+impl PartialOrd for OpenNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl NavGrid {
+    pub fn new(min: Vec2, max: Vec2, cell_size: f32) -> Self {
+        let width = ((max.x - min.x) / cell_size).ceil() as i32;
+        let height = ((max.y - min.y) / cell_size).ceil() as i32;
+        Self {
+            origin: min,
+            cell_size,
+            width,
+            height,
+            blocked: vec![false; (width * height) as usize],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.blocked.fill(false);
+    }
+
+    // Block every cell whose centre lies inside the rectangle
+    pub fn block_rect(&mut self, rect: Rect) {
+        let min = ((rect.min - self.origin) / self.cell_size).floor().as_ivec2().max(IVec2::ZERO);
+        let max = ((rect.max - self.origin) / self.cell_size)
+            .floor()
+            .as_ivec2()
+            .min(IVec2::new(self.width - 1, self.height - 1));
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                let cell = IVec2::new(x, y);
+                if rect.contains(self.cell_center(cell)) {
+                    let index = self.index(cell);
+                    self.blocked[index] = true;
+                }
+            }
+        }
+    }
+
+    pub fn cell_of(&self, position: Vec2) -> Option<IVec2> {
+        let local = (position - self.origin) / self.cell_size;
+        let cell = IVec2::new(local.x.floor() as i32, local.y.floor() as i32);
+        self.in_bounds(cell).then_some(cell)
+    }
+
+    pub fn cell_center(&self, cell: IVec2) -> Vec2 {
+        self.origin + (cell.as_vec2() + Vec2::splat(0.5)) * self.cell_size
+    }
+
+    pub fn is_walkable(&self, cell: IVec2) -> bool {
+        self.in_bounds(cell) && !self.blocked[self.index(cell)]
+    }
+
+    /// A* from `start` to `goal`, returns the waypoints to follow (ending at `goal`).
+    /// A goal inside an obstacle is approached via the closest walkable cell.
+    pub fn find_path(&self, start: Vec2, goal: Vec2) -> Option<Vec<Vec2>> {
+        if self.line_of_sight(start, goal) {
+            return Some(vec![goal]);
+        }
+
+        let start_cell = self.cell_of(start)?;
+        let goal_cell = self.nearest_walkable(self.cell_of(goal)?)?;
+
+        let cell_count = self.blocked.len();
+        let mut best_cost = vec![f32::INFINITY; cell_count];
+        let mut came_from: Vec<Option<IVec2>> = vec![None; cell_count];
+        let mut open = BinaryHeap::new();
+
+        best_cost[self.index(start_cell)] = 0.0;
+        open.push(OpenNode { cost: heuristic(start_cell, goal_cell), cell: start_cell });
+
+        while let Some(OpenNode { cell, .. }) = open.pop() {
+            if cell == goal_cell {
+                return Some(self.build_path(start, goal, goal_cell, &came_from));
+            }
+
+            let cost_here = best_cost[self.index(cell)];
+            for offset in NEIGHBOURS {
+                let next = cell + offset;
+                // The start cell may be blocked (unit standing on an edge), every other cell must be free
+                if !self.is_walkable(next) {
+                    continue;
+                }
+                // Don't cut corners diagonally past blocked cells
+                if offset.x != 0 && offset.y != 0
+                    && (!self.is_walkable(cell + IVec2::new(offset.x, 0)) || !self.is_walkable(cell + IVec2::new(0, offset.y)))
+                {
+                    continue;
+                }
+
+                let step = if offset.x != 0 && offset.y != 0 { std::f32::consts::SQRT_2 } else { 1.0 };
+                let cost = cost_here + step;
+                let next_index = self.index(next);
+                if cost < best_cost[next_index] {
+                    best_cost[next_index] = cost;
+                    came_from[next_index] = Some(cell);
+                    open.push(OpenNode { cost: cost + heuristic(next, goal_cell), cell: next });
+                }
+            }
+        }
+
+        None
+    }
+
+    // Walk the line between two points in half cell steps and make sure it never crosses a blocked cell
+    pub fn line_of_sight(&self, from: Vec2, to: Vec2) -> bool {
+        let distance = from.distance(to);
+        let steps = (distance / (self.cell_size * 0.5)).ceil().max(1.0) as i32;
+        (1..=steps).all(|step| {
+            let point = from.lerp(to, step as f32 / steps as f32);
+            match self.cell_of(point) {
+                Some(cell) => self.is_walkable(cell) || point.distance(to) < self.cell_size,
+                None => true, // outside the grid there are no obstacles
+            }
+        })
+    }
+
+    fn nearest_walkable(&self, cell: IVec2) -> Option<IVec2> {
+        if self.is_walkable(cell) {
+            return Some(cell);
+        }
+        // Search growing rings around the cell
+        for radius in 1..self.width.max(self.height) {
+            let mut best: Option<(f32, IVec2)> = None;
+            for y in -radius..=radius {
+                for x in -radius..=radius {
+                    if x.abs() != radius && y.abs() != radius {
+                        continue;
+                    }
+                    let candidate = cell + IVec2::new(x, y);
+                    if self.is_walkable(candidate) {
+                        let distance = candidate.as_vec2().distance(cell.as_vec2());
+                        if best.is_none_or(|(best_distance, _)| distance < best_distance) {
+                            best = Some((distance, candidate));
+                        }
+                    }
+                }
+            }
+            if let Some((_, found)) = best {
+                return Some(found);
+            }
+        }
+        None
+    }
+
+    fn build_path(&self, start: Vec2, goal: Vec2, goal_cell: IVec2, came_from: &[Option<IVec2>]) -> Vec<Vec2> {
+        let mut cells = vec![goal_cell];
+        let mut current = goal_cell;
+        while let Some(previous) = came_from[self.index(current)] {
+            cells.push(previous);
+            current = previous;
+        }
+        cells.reverse();
+
+        let mut points: Vec<Vec2> = cells.into_iter().skip(1).map(|cell| self.cell_center(cell)).collect();
+        points.push(goal);
+
+        // String pulling: skip every waypoint that can be seen from the last kept point
+        let mut smoothed = Vec::new();
+        let mut anchor = start;
+        let mut i = 0;
+        while i < points.len() {
+            let mut furthest = i;
+            while furthest + 1 < points.len() && self.line_of_sight(anchor, points[furthest + 1]) {
+                furthest += 1;
+            }
+            smoothed.push(points[furthest]);
+            anchor = points[furthest];
+            i = furthest + 1;
+        }
+        smoothed
+    }
+
+    fn in_bounds(&self, cell: IVec2) -> bool {
+        cell.x >= 0 && cell.y >= 0 && cell.x < self.width && cell.y < self.height
+    }
+
+    fn index(&self, cell: IVec2) -> usize {
+        (cell.y * self.width + cell.x) as usize
+    }
+}
+
+const NEIGHBOURS: [IVec2; 8] = [
+    IVec2::new(1, 0),
+    IVec2::new(-1, 0),
+    IVec2::new(0, 1),
+    IVec2::new(0, -1),
+    IVec2::new(1, 1),
+    IVec2::new(1, -1),
+    IVec2::new(-1, 1),
+    IVec2::new(-1, -1),
+];
+
+// Octile distance, admissible for 8-way movement
+fn heuristic(a: IVec2, b: IVec2) -> f32 {
+    let d = (a - b).abs();
+    let (min, max) = (d.x.min(d.y) as f32, d.x.max(d.y) as f32);
+    max - min + min * std::f32::consts::SQRT_2
+}
+
+// Re-block the grid whenever an obstacle is added, moved or removed
+pub fn nav_grid_rebuild_system(
+    mut nav_grid: ResMut<NavGrid>,
+    obstacle_query: Query<(&Transform, &Size), With<Obstacle>>,
+    changed_query: Query<(), (With<Obstacle>, Or<(Added<Obstacle>, Changed<Transform>, Changed<Size>)>)>,
+    mut removed: RemovedComponents<Obstacle>,
+) {
+    let removed_any = removed.read().count() > 0;
+    if changed_query.is_empty() && !removed_any {
+        return;
+    }
+
+    nav_grid.clear();
+    for (transform, size) in obstacle_query.iter() {
+        let half_size = size.0 / 2.0 + Vec2::splat(OBSTACLE_CLEARANCE);
+        let center = transform.translation.xy();
+        nav_grid.block_rect(Rect::from_corners(center - half_size, center + half_size));
+    }
+}
+
+// Plans a path for every moving unit whose target has moved more than a cell since the last plan
+pub fn path_planning_system(
+    nav_grid: Res<NavGrid>,
+    query: Query<(Entity, &Transform, &Movement, Option<&NavPath>)>,
+    mut removed_movement: RemovedComponents<Movement>,
+    mut commands: Commands,
+) {
+    for entity in removed_movement.read() {
+        if let Ok(mut entity_commands) = commands.get_entity(entity) {
+            entity_commands.try_remove::<NavPath>();
+        }
+    }
+
+    for (entity, transform, movement, maybe_path) in query.iter() {
+        let needs_plan = match maybe_path {
+            Some(path) => path.goal.distance(movement.target) > nav_grid.cell_size,
+            None => true,
+        };
+        if !needs_plan {
+            continue;
+        }
+
+        // Without a path the unit falls back to walking straight at its target
+        let waypoints = nav_grid
+            .find_path(transform.translation.xy(), movement.target)
+            .unwrap_or_default();
+        commands.entity(entity).insert(NavPath {
+            waypoints,
+            goal: movement.target,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 100x100 world in 10 unit cells with a wall in the middle that leaves a gap at the top
+    fn walled_grid() -> NavGrid {
+        let mut grid = NavGrid::new(Vec2::ZERO, Vec2::splat(100.0), 10.0);
+        grid.block_rect(Rect::new(40.0, 0.0, 60.0, 80.0));
+        grid
+    }
+
+    fn crosses_wall(grid: &NavGrid, from: Vec2, path: &[Vec2]) -> bool {
+        std::iter::once(from)
+            .chain(path.iter().copied())
+            .collect::<Vec<_>>()
+            .windows(2)
+            .any(|segment| !grid.line_of_sight(segment[0], segment[1]))
+    }
+
+    #[test]
+    fn block_rect_blocks_cells_inside_the_rect() {
+        let grid = walled_grid();
+        assert!(!grid.is_walkable(IVec2::new(4, 0)));
+        assert!(!grid.is_walkable(IVec2::new(5, 7)));
+        assert!(grid.is_walkable(IVec2::new(4, 8)));
+        assert!(grid.is_walkable(IVec2::new(3, 0)));
+        assert!(!grid.is_walkable(IVec2::new(-1, 0)));
+    }
+
+    #[test]
+    fn line_of_sight_is_broken_by_the_wall() {
+        let grid = walled_grid();
+        assert!(!grid.line_of_sight(Vec2::new(10.0, 20.0), Vec2::new(90.0, 20.0)));
+        assert!(grid.line_of_sight(Vec2::new(10.0, 90.0), Vec2::new(90.0, 90.0)));
+        assert!(grid.line_of_sight(Vec2::new(10.0, 10.0), Vec2::new(30.0, 70.0)));
+    }
+
+    #[test]
+    fn open_ground_is_a_straight_line() {
+        let grid = walled_grid();
+        let goal = Vec2::new(30.0, 70.0);
+        assert_eq!(grid.find_path(Vec2::new(10.0, 10.0), goal), Some(vec![goal]));
+    }
+
+    #[test]
+    fn find_path_goes_around_the_wall() {
+        let grid = walled_grid();
+        let start = Vec2::new(15.0, 15.0);
+        let goal = Vec2::new(85.0, 15.0);
+        let path = grid.find_path(start, goal).unwrap();
+
+        assert_eq!(path.last(), Some(&goal));
+        assert!(path.len() > 1);
+        assert!(!crosses_wall(&grid, start, &path));
+        // The only way past is through the gap above the wall
+        assert!(path.iter().any(|waypoint| waypoint.y > 80.0));
+    }
+
+    #[test]
+    fn goal_inside_an_obstacle_is_approached_from_outside() {
+        let grid = walled_grid();
+        let start = Vec2::new(15.0, 15.0);
+        let goal = Vec2::new(50.0, 40.0);
+        let path = grid.find_path(start, goal).unwrap();
+
+        assert_eq!(path.last(), Some(&goal));
+        let before_goal = path.len().checked_sub(2).map_or(start, |index| path[index]);
+        assert!(grid.is_walkable(grid.cell_of(before_goal).unwrap()));
+    }
+
+    #[test]
+    fn no_path_through_a_closed_wall() {
+        let mut grid = walled_grid();
+        grid.block_rect(Rect::new(40.0, 0.0, 60.0, 100.0));
+        assert_eq!(grid.find_path(Vec2::new(15.0, 15.0), Vec2::new(85.0, 15.0)), None);
+    }
+}
@@ -0,0 +1,79 @@
+use bevy::prelude::*;
+use crate::collision::SpatialQuery;
+use crate::damage::Damage;
+use crate::{deal_damage, DamageTarget, GameStates, Size};
+
+const PROJECTILE_SIZE: f32 = 10.0;
+const PLAYER_PROJECTILE_COLOR: Color = Color::srgb(0.3, 0.9, 1.0);
+const ENEMY_PROJECTILE_COLOR: Color = Color::srgb(1.0, 0.5, 0.1);
+const MISS_GRACE_FACTOR: f32 = 1.5; // projectiles keep flying a bit past their aim point before fizzling out
+
+#[derive(Component)]
+pub struct Projectile {
+    velocity: Vec2,
+    damage: Damage,
+    source: Entity,
+    hits_enemies: bool, // fired by the player's side, so it collides with enemies only (and the other way around)
+    lifetime: Timer,
+}
+
+// Fires a projectile from `from` at the current position of the target, it hits whatever is in its way first
+pub fn spawn_projectile(
+    commands: &mut Commands,
+    source: Entity,
+    from: Vec2,
+    aim_at: Vec2,
+    speed: f32,
+    damage: Damage,
+    hits_enemies: bool,
+) {
+    let offset = aim_at - from;
+    let travel_time = offset.length() / speed.max(1.0);
+    let color = if hits_enemies { PLAYER_PROJECTILE_COLOR } else { ENEMY_PROJECTILE_COLOR };
+
+    commands.spawn((
+        Projectile {
+            velocity: offset.normalize_or(Vec2::Y) * speed,
+            damage,
+            source,
+            hits_enemies,
+            lifetime: Timer::from_seconds(travel_time * MISS_GRACE_FACTOR, TimerMode::Once),
+        },
+        Sprite::from_color(color, Vec2::splat(PROJECTILE_SIZE)),
+        Transform::from_xyz(from.x, from.y, 4.0),
+        Name::new("Projectile"),
+    ));
+}
+
+// Moves projectiles and checks them against the hitboxes of everything on the other side
+pub fn projectile_system(
+    mut projectile_query: Query<(Entity, &mut Transform, &mut Projectile), Without<Size>>,
+    mut target_query: Query<DamageTarget, Without<Projectile>>,
+    spatial: SpatialQuery,
+    time: Res<Time>,
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<GameStates>>,
+) {
+    for (entity, mut transform, mut projectile) in projectile_query.iter_mut() {
+        transform.translation += (projectile.velocity * time.delta_secs()).extend(0.0);
+        let position = transform.translation.xy();
+
+        let hit = spatial.at_point(position).find(|&target| {
+            target != projectile.source
+                && target_query.get(target).is_ok_and(|damage_target| {
+                    damage_target.is_enemy == projectile.hits_enemies && !damage_target.health.is_dead()
+                })
+        });
+
+        if let Some(mut damage_target) = hit.and_then(|target| target_query.get_mut(target).ok()) {
+            deal_damage(&mut commands, &mut next_state, &mut damage_target, projectile.damage, projectile.source);
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        projectile.lifetime.tick(time.delta());
+        if projectile.lifetime.is_finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use bevy::prelude::*;
+use crate::bob_state::{BobState, BobStateCommands, Idling, Repairing};
+use crate::message_log::GameLogCommands;
+use crate::{AtTarget, ComponentsInventory, Health, HomeBase, MenuButton};
+
+const REPAIR_PART_COST: u32 = 2;
+const REPAIR_AMOUNT: f32 = 100.0; // health restored per repair order
+const REPAIR_COOLDOWN_SECONDS: f32 = 15.0;
+const BASE_REPAIR_PER_SECOND: f32 = 10.0;
+const BOB_REPAIR_BONUS_PER_SECOND: f32 = 5.0; // extra repair speed for every Bob working at the base
+
+#[derive(Event)]
+pub struct RepairBaseEvent;
+
+/// Pending home base repairs, paid for up front and restored over time
+#[derive(Resource)]
+pub struct BaseRepair {
+    pool: f32, // health still to be restored
+    cooldown: Timer,
+}
+
+impl Default for BaseRepair {
+    fn default() -> Self {
+        let mut cooldown = Timer::from_seconds(REPAIR_COOLDOWN_SECONDS, TimerMode::Once);
+        // The first repair is available right away
+        cooldown.finish();
+        Self { pool: 0.0, cooldown }
+    }
+}
+
+impl BaseRepair {
+    pub fn is_repairing(&self) -> bool {
+        self.pool > 0.0
+    }
+
+    pub fn cooldown_remaining(&self) -> f32 {
+        self.cooldown.remaining_secs()
+    }
+}
+
+pub fn on_repair_base(
+    _trigger: On<RepairBaseEvent>,
+    mut base_repair: ResMut<BaseRepair>,
+    mut inventory_query: Query<&mut ComponentsInventory>,
+    home_base_query: Query<&Health, With<HomeBase>>,
+    idle_query: Query<Entity, With<Idling>>,
+    mut commands: Commands,
+) {
+    if !base_repair.cooldown.is_finished() {
+        commands.log_warning(format!("Repairs are on cooldown for {:.0}s", base_repair.cooldown_remaining()));
+        return;
+    }
+    let Ok(health) = home_base_query.single() else {
+        return;
+    };
+    // Never queue more repairs than the base can take
+    let missing = health.max - health.current - base_repair.pool;
+    if missing <= 0.0 {
+        commands.log_info("The home base doesn't need repairs");
+        return;
+    }
+    let Ok(mut inventory) = inventory_query.single_mut() else {
+        commands.log_error("No inventory found!");
+        return;
+    };
+    if !inventory.take(REPAIR_PART_COST) {
+        commands.log_warning(format!("Repairing needs {} parts!", REPAIR_PART_COST));
+        return;
+    }
+
+    base_repair.pool += REPAIR_AMOUNT.min(missing);
+    base_repair.cooldown.reset();
+
+    // An idle Bob walks over to help out
+    if let Some(entity) = idle_query.iter().next() {
+        commands.entity(entity).set_bob_state(BobState::Repairing);
+        commands.log_info("Repairing the home base, a Bob went to help");
+    } else {
+        commands.log_info("Repairing the home base");
+    }
+}
+
+pub fn base_repair_system(
+    mut base_repair: ResMut<BaseRepair>,
+    mut home_base_query: Query<&mut Health, With<HomeBase>>,
+    helper_query: Query<(), (With<Repairing>, With<AtTarget>)>,
+    time: Res<Time>,
+) {
+    base_repair.cooldown.tick(time.delta());
+    if !base_repair.is_repairing() {
+        return;
+    }
+    let Ok(mut health) = home_base_query.single_mut() else {
+        return;
+    };
+
+    let rate = BASE_REPAIR_PER_SECOND + BOB_REPAIR_BONUS_PER_SECOND * helper_query.iter().count() as f32;
+    let amount = (rate * time.delta_secs())
+        .min(base_repair.pool)
+        .min(health.max - health.current);
+    health.current += amount;
+    base_repair.pool -= amount;
+
+    // Stop once the base is fully repaired, even if repairs were still pending
+    if health.current >= health.max {
+        base_repair.pool = 0.0;
+    }
+}
+
+// Shows the remaining cooldown on the repair button
+pub fn repair_button_system(
+    base_repair: Res<BaseRepair>,
+    button_query: Query<(&MenuButton, &Children)>,
+    mut text_query: Query<&mut Text>,
+) {
+    let label = if base_repair.cooldown.is_finished() {
+        "Repair!".to_string()
+    } else {
+        format!("{:.0}s", base_repair.cooldown_remaining().ceil())
+    };
+
+    for (button, children) in button_query.iter() {
+        if !matches!(button, MenuButton::Repair) {
+            continue;
+        }
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(child)
+                && text.0 != label
+            {
+                text.0 = label.clone();
+            }
+        }
+    }
+}
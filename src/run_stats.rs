@@ -0,0 +1,58 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::difficulty::{Difficulty, DifficultyPreset};
+use crate::map::BattleMap;
+use crate::message_log::GameLogCommands;
+use crate::{GameStates, MatchClock};
+
+const RUN_STATS_PATH: &str = "run_stats.ron"; // next to wherever the game is started from
+
+/// One finished run, appended to `run_stats.ron`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RunRecord {
+    pub difficulty: DifficultyPreset,
+    pub seed: u64,
+    pub won: bool,
+    pub match_seconds: f32,
+}
+
+// Every run so far, oldest first. None if the file exists but can't be read, so it is not overwritten
+fn load_runs() -> Option<Vec<RunRecord>> {
+    let Ok(text) = std::fs::read_to_string(RUN_STATS_PATH) else {
+        return Some(Vec::new());
+    };
+    ron::from_str(&text)
+        .map_err(|error| warn!("Could not read {}: {}", RUN_STATS_PATH, error))
+        .ok()
+}
+
+// Runs when the game is won or lost
+pub fn record_run_system(
+    state: Res<State<GameStates>>,
+    difficulty: Res<Difficulty>,
+    map: Res<BattleMap>,
+    match_clock: Res<MatchClock>,
+    mut commands: Commands,
+) {
+    let Some(mut runs) = load_runs() else {
+        commands.log_warning(format!("{} is damaged, this run was not recorded", RUN_STATS_PATH));
+        return;
+    };
+    runs.push(RunRecord {
+        difficulty: difficulty.preset,
+        seed: map.seed,
+        won: *state.get() == GameStates::Win,
+        match_seconds: match_clock.elapsed,
+    });
+
+    let result = ron::ser::to_string_pretty(&runs, ron::ser::PrettyConfig::default())
+        .map_err(|error| error.to_string())
+        .and_then(|text| std::fs::write(RUN_STATS_PATH, text).map_err(|error| error.to_string()));
+    match result {
+        Ok(()) => {
+            let wins = runs.iter().filter(|run| run.won && run.difficulty == difficulty.preset).count();
+            commands.log_info(format!("Run recorded, {} win(s) on {} so far", wins, difficulty.preset.label()));
+        },
+        Err(error) => commands.log_warning(format!("Could not save {}: {}", RUN_STATS_PATH, error)),
+    }
+}
@@ -0,0 +1,364 @@
+use bevy::prelude::*;
+use crate::damage::{Damage, DamageType};
+use crate::{deal_damage, DamageTarget, GameStates, Size};
+
+const MAX_BURN_STACKS: usize = 3;
+const BURN_TICK: f32 = 1.0; // burns deal their damage once per second instead of every frame
+const MAX_SHIELD: f32 = 200.0;
+const ICON_SPACING: f32 = 60.0;
+const ICON_OFFSET: f32 = 15.0; // gap between the top of the unit and its icons
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusKind {
+    Stun,   // no moving, no attacking
+    Slow,   // magnitude is the fraction of speed and attack rate that is lost
+    Burn,   // magnitude is fire damage per second
+    Shield, // magnitude is the damage that is still absorbed
+}
+
+impl StatusKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            StatusKind::Stun => "STUN",
+            StatusKind::Slow => "SLOW",
+            StatusKind::Burn => "BURN",
+            StatusKind::Shield => "SHIELD",
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            StatusKind::Stun => Color::srgb(1.0, 1.0, 0.3),
+            StatusKind::Slow => Color::srgb(0.5, 0.7, 1.0),
+            StatusKind::Burn => Color::srgb(1.0, 0.5, 0.1),
+            StatusKind::Shield => Color::srgb(0.3, 1.0, 1.0),
+        }
+    }
+}
+
+/// A timed effect, either carried by a hit (see `Damage::with_status`) or applied directly with ApplyStatusEvent
+#[derive(Debug, Clone, Copy)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    pub magnitude: f32,
+    pub duration: f32,
+    pub source: Option<Entity>,
+    remaining: f32,
+    since_tick: f32,
+}
+
+impl StatusEffect {
+    fn new(kind: StatusKind, magnitude: f32, duration: f32) -> Self {
+        Self {
+            kind,
+            magnitude,
+            duration,
+            source: None,
+            remaining: duration,
+            since_tick: 0.0,
+        }
+    }
+
+    pub fn stun(duration: f32) -> Self {
+        Self::new(StatusKind::Stun, 1.0, duration)
+    }
+
+    pub fn slow(fraction: f32, duration: f32) -> Self {
+        Self::new(StatusKind::Slow, fraction.clamp(0.0, 1.0), duration)
+    }
+
+    pub fn burn(damage_per_second: f32, duration: f32) -> Self {
+        Self::new(StatusKind::Burn, damage_per_second, duration)
+    }
+
+    pub fn shield(amount: f32, duration: f32) -> Self {
+        Self::new(StatusKind::Shield, amount, duration)
+    }
+
+    pub fn with_source(mut self, source: Entity) -> Self {
+        self.source = Some(source);
+        self
+    }
+}
+
+/// Every effect currently active on an entity
+#[derive(Component, Debug, Default)]
+pub struct StatusEffects {
+    effects: Vec<StatusEffect>,
+}
+
+impl StatusEffects {
+    // Stacking rules: stuns and slows refresh (the stronger slow wins), burns stack up to
+    // MAX_BURN_STACKS replacing the oldest one, shields add up to MAX_SHIELD
+    pub fn apply(&mut self, effect: StatusEffect) {
+        match effect.kind {
+            StatusKind::Stun | StatusKind::Slow => {
+                if let Some(existing) = self.effects.iter_mut().find(|existing| existing.kind == effect.kind) {
+                    existing.magnitude = existing.magnitude.max(effect.magnitude);
+                    existing.remaining = existing.remaining.max(effect.duration);
+                    return;
+                }
+            },
+            StatusKind::Burn => {
+                if self.stacks(StatusKind::Burn) >= MAX_BURN_STACKS
+                    && let Some(oldest) = self.effects
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, existing)| existing.kind == StatusKind::Burn)
+                        .min_by(|a, b| a.1.remaining.total_cmp(&b.1.remaining))
+                        .map(|(index, _)| index)
+                {
+                    self.effects.remove(oldest);
+                }
+            },
+            StatusKind::Shield => {
+                if let Some(existing) = self.effects.iter_mut().find(|existing| existing.kind == StatusKind::Shield) {
+                    existing.magnitude = (existing.magnitude + effect.magnitude).min(MAX_SHIELD);
+                    existing.remaining = existing.remaining.max(effect.duration);
+                    return;
+                }
+            },
+        }
+        self.effects.push(effect);
+    }
+
+    pub fn stacks(&self, kind: StatusKind) -> usize {
+        self.effects.iter().filter(|effect| effect.kind == kind).count()
+    }
+
+    pub fn is_stunned(&self) -> bool {
+        self.stacks(StatusKind::Stun) > 0
+    }
+
+    // Applied to movement speed and to how fast attack cooldowns recover
+    pub fn speed_multiplier(&self) -> f32 {
+        if self.is_stunned() {
+            return 0.0;
+        }
+        self.effects
+            .iter()
+            .filter(|effect| effect.kind == StatusKind::Slow)
+            .fold(1.0, |multiplier, slow| multiplier * (1.0 - slow.magnitude))
+    }
+
+    // Lets shields soak up damage, returns what gets through
+    pub fn absorb(&mut self, mut damage: f32) -> f32 {
+        for shield in self.effects.iter_mut().filter(|effect| effect.kind == StatusKind::Shield) {
+            let absorbed = shield.magnitude.min(damage);
+            shield.magnitude -= absorbed;
+            damage -= absorbed;
+        }
+        self.effects.retain(|effect| effect.kind != StatusKind::Shield || effect.magnitude > 0.0);
+        damage
+    }
+
+    // Counts down every effect, returns the burn damage that is due this frame with its source
+    pub fn tick(&mut self, delta: f32) -> Vec<(f32, Option<Entity>)> {
+        let mut burns = Vec::new();
+        for effect in self.effects.iter_mut() {
+            effect.remaining -= delta;
+            if effect.kind == StatusKind::Burn {
+                effect.since_tick += delta;
+                if effect.since_tick >= BURN_TICK {
+                    effect.since_tick -= BURN_TICK;
+                    burns.push((effect.magnitude * BURN_TICK, effect.source));
+                }
+            }
+        }
+        self.effects.retain(|effect| effect.remaining > 0.0);
+        burns
+    }
+
+    // What the icons above the unit show, burns with their stack count
+    fn icons(&self) -> Vec<(StatusKind, usize)> {
+        let mut icons: Vec<(StatusKind, usize)> = Vec::new();
+        for effect in self.effects.iter() {
+            match icons.iter_mut().find(|(kind, _)| *kind == effect.kind) {
+                Some((_, count)) => *count += 1,
+                None => icons.push((effect.kind, 1)),
+            }
+        }
+        icons
+    }
+}
+
+/// Puts a status effect on the entity, following the StatusEffects stacking rules
+#[derive(EntityEvent)]
+pub struct ApplyStatusEvent {
+    pub entity: Entity,
+    pub effect: StatusEffect,
+}
+
+pub fn on_apply_status(
+    trigger: On<ApplyStatusEvent>,
+    mut query: Query<Option<&mut StatusEffects>>,
+    mut commands: Commands,
+) {
+    let Ok(maybe_effects) = query.get_mut(trigger.entity) else {
+        return;
+    };
+    match maybe_effects {
+        Some(mut effects) => effects.apply(trigger.effect),
+        None => {
+            let mut effects = StatusEffects::default();
+            effects.apply(trigger.effect);
+            commands.entity(trigger.entity).insert(effects);
+        },
+    }
+}
+
+// Counts every effect down and deals burn damage through the usual damage path
+pub fn status_effect_system(
+    mut query: Query<DamageTarget, With<StatusEffects>>,
+    time: Res<Time>,
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<GameStates>>,
+) {
+    for mut target in query.iter_mut() {
+        let Some(effects) = target.status.as_mut() else {
+            continue;
+        };
+        for (amount, source) in effects.tick(time.delta_secs()) {
+            let burn = Damage::new(amount, DamageType::Fire);
+            let source = source.unwrap_or(target.entity);
+            deal_damage(&mut commands, &mut next_state, &mut target, burn, source);
+        }
+    }
+}
+
+// The row of effect labels floating above a unit
+#[derive(Component)]
+pub struct StatusIcons {
+    row: Entity,
+    shown: Vec<(StatusKind, usize)>,
+}
+
+// Rebuilds the icon row when the set of active effects changes, not every time a timer ticks
+pub fn status_icon_system(
+    query: Query<(Entity, &StatusEffects, &Size, Option<&StatusIcons>), Changed<StatusEffects>>,
+    mut commands: Commands,
+) {
+    for (entity, effects, size, maybe_icons) in query.iter() {
+        let icons = effects.icons();
+        if maybe_icons.is_some_and(|shown| shown.shown == icons) {
+            continue;
+        }
+        if let Some(shown) = maybe_icons {
+            commands.entity(shown.row).despawn();
+        }
+        if icons.is_empty() {
+            commands.entity(entity).remove::<StatusIcons>();
+            continue;
+        }
+
+        let first_x = -(icons.len() as f32 - 1.0) * ICON_SPACING / 2.0;
+        let row = commands.spawn((
+            Transform::from_xyz(0.0, size.0.y / 2.0 + ICON_OFFSET, 1.0),
+            Visibility::default(),
+            ChildOf(entity),
+            Name::new("Status Icons"),
+        )).with_children(|row| {
+            for (index, (kind, count)) in icons.iter().enumerate() {
+                let label = if *count > 1 { format!("{} x{}", kind.label(), count) } else { kind.label().to_string() };
+                row.spawn((
+                    Text2d::new(label),
+                    TextFont {
+                        font_size: 12.0,
+                        ..default()
+                    },
+                    TextColor(kind.color()),
+                    Transform::from_xyz(first_x + index as f32 * ICON_SPACING, 0.0, 0.0),
+                ));
+            }
+        }).id();
+        commands.entity(entity).insert(StatusIcons { row, shown: icons });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stuns_refresh_instead_of_stacking() {
+        let mut effects = StatusEffects::default();
+        effects.apply(StatusEffect::stun(1.0));
+        effects.apply(StatusEffect::stun(3.0));
+        assert_eq!(effects.stacks(StatusKind::Stun), 1);
+        assert_eq!(effects.speed_multiplier(), 0.0);
+
+        // The longer duration wins
+        effects.tick(2.0);
+        assert!(effects.is_stunned());
+        effects.tick(1.5);
+        assert!(!effects.is_stunned());
+    }
+
+    #[test]
+    fn the_stronger_slow_wins() {
+        let mut effects = StatusEffects::default();
+        effects.apply(StatusEffect::slow(0.25, 2.0));
+        effects.apply(StatusEffect::slow(0.5, 1.0));
+        assert_eq!(effects.stacks(StatusKind::Slow), 1);
+        assert_eq!(effects.speed_multiplier(), 0.5);
+    }
+
+    #[test]
+    fn burns_stack_up_to_the_limit_replacing_the_oldest() {
+        let mut effects = StatusEffects::default();
+        for duration in [2.0, 3.0, 4.0, 5.0] {
+            effects.apply(StatusEffect::burn(10.0, duration));
+        }
+        assert_eq!(effects.stacks(StatusKind::Burn), MAX_BURN_STACKS);
+
+        // The 2 second burn was replaced, so all three are still burning after it would have run out
+        effects.tick(2.5);
+        assert_eq!(effects.stacks(StatusKind::Burn), MAX_BURN_STACKS);
+    }
+
+    #[test]
+    fn burns_deal_damage_once_per_tick() {
+        let mut effects = StatusEffects::default();
+        effects.apply(StatusEffect::burn(10.0, 3.0));
+
+        assert!(effects.tick(0.5).is_empty());
+        assert_eq!(effects.tick(0.5), vec![(10.0, None)]);
+        assert!(effects.tick(0.9).is_empty());
+    }
+
+    #[test]
+    fn effects_expire() {
+        let mut effects = StatusEffects::default();
+        effects.apply(StatusEffect::slow(0.5, 1.0));
+        effects.apply(StatusEffect::burn(5.0, 2.0));
+
+        effects.tick(1.0);
+        assert_eq!(effects.stacks(StatusKind::Slow), 0);
+        assert_eq!(effects.speed_multiplier(), 1.0);
+        assert_eq!(effects.stacks(StatusKind::Burn), 1);
+
+        effects.tick(1.0);
+        assert_eq!(effects.stacks(StatusKind::Burn), 0);
+    }
+
+    #[test]
+    fn shields_add_up_and_soak_damage() {
+        let mut effects = StatusEffects::default();
+        effects.apply(StatusEffect::shield(30.0, 5.0));
+        effects.apply(StatusEffect::shield(20.0, 5.0));
+        assert_eq!(effects.stacks(StatusKind::Shield), 1);
+
+        assert_eq!(effects.absorb(40.0), 0.0);
+        assert_eq!(effects.absorb(25.0), 15.0);
+        // Used up shields are gone
+        assert_eq!(effects.stacks(StatusKind::Shield), 0);
+    }
+
+    #[test]
+    fn shields_are_capped() {
+        let mut effects = StatusEffects::default();
+        effects.apply(StatusEffect::shield(MAX_SHIELD, 5.0));
+        effects.apply(StatusEffect::shield(50.0, 5.0));
+        assert_eq!(effects.absorb(MAX_SHIELD + 10.0), 10.0);
+    }
+}
@@ -0,0 +1,138 @@
+use bevy::prelude::*;
+use crate::message_log::GameLogCommands;
+use crate::navigation::Obstacle;
+use crate::collision::{Collider, SpatialQuery};
+use crate::damage::{Armour, Damage, DamageType, Resistances};
+use crate::{Attack, ComponentsInventory, Enemy, Health, HomeBase, Size};
+
+const TURRET_COLOR: Color = Color::srgb(0.4, 0.45, 0.5);
+const TURRET_PART_COST: u32 = 5;
+const TURRET_HEALTH: f32 = 150.0;
+const TURRET_ARMOUR: f32 = 0.3;
+const TURRET_RANGE: f32 = 300.0; // measured between hitbox edges
+const TURRET_DAMAGE: f32 = 8.0;
+const TURRET_COOLDOWN: f32 = 1.5;
+const TURRET_PROJECTILE_SPEED: f32 = 500.0;
+const EMPLACEMENT_MARGIN: f32 = 50.0; // distance between the base edge and a turret centre
+
+// Spots around the home base turrets can be built on, relative to the base edges
+const EMPLACEMENTS: [Vec2; 6] = [
+    Vec2::new(-1.0, 1.0),
+    Vec2::new(1.0, 1.0),
+    Vec2::new(-1.0, 0.0),
+    Vec2::new(1.0, 0.0),
+    Vec2::new(-1.0, -1.0),
+    Vec2::new(1.0, -1.0),
+];
+
+#[derive(Event)]
+pub struct BuildTurretEvent;
+
+// A player built turret standing on one of the EMPLACEMENTS
+#[derive(Component)]
+pub struct Emplacement(pub usize);
+
+/// A static defence that picks the closest enemy in range and hands it to attacking_system through `Attack`
+#[derive(Component)]
+pub struct Turret {
+    pub range: f32,
+    pub damage: Damage,
+    pub cooldown: f32,
+}
+
+pub fn spawn_turret(commands: &mut Commands, position: Vec2, turret: Turret, health: f32, name: &str) -> Entity {
+    let turret_size = Size::square(50.0);
+    let turret_size_vec = turret_size.0;
+    commands.spawn((
+        turret,
+        turret_size,
+        Health::new(health),
+        // Steel plating shrugs off bullets, it conducts electricity though
+        Armour(TURRET_ARMOUR),
+        Resistances { kinetic: 0.3, electric: -0.2, ..default() },
+        Sprite::from_color(TURRET_COLOR, turret_size_vec),
+        Transform::from_xyz(position.x, position.y, 3.0),
+        Collider::solid(),
+        Name::new(name.to_string()),
+    )).id()
+}
+
+pub fn turret_targeting_system(
+    turret_query: Query<(Entity, &Turret, Option<&Attack>)>,
+    enemy_query: Query<&Health, With<Enemy>>,
+    spatial: SpatialQuery,
+    mut commands: Commands,
+) {
+    for (entity, turret, maybe_attack) in turret_query.iter() {
+        let Some(hitbox) = spatial.hitbox(entity) else {
+            continue;
+        };
+        let closest = spatial
+            .within(hitbox, turret.range)
+            .filter(|(enemy, _)| enemy_query.get(*enemy).is_ok_and(|health| !health.is_dead()))
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+
+        match (closest, maybe_attack) {
+            // Keep the current target while it stays the closest one
+            (Some((enemy, _)), Some(attack)) if attack.target_entity == enemy => {},
+            (Some((enemy, _)), _) => {
+                commands.entity(entity).insert(Attack {
+                    current_cooldown: maybe_attack.map_or(0.0, |attack| attack.current_cooldown),
+                    ..Attack::ranged(enemy, turret.damage, turret.cooldown, turret.range, TURRET_PROJECTILE_SPEED)
+                });
+            },
+            (None, Some(_)) => {
+                commands.entity(entity).remove::<Attack>();
+            },
+            (None, None) => {},
+        }
+    }
+}
+
+pub fn on_build_turret(
+    _trigger: On<BuildTurretEvent>,
+    mut inventory_query: Query<&mut ComponentsInventory>,
+    home_base_query: Query<(&Transform, &Size), With<HomeBase>>,
+    emplacement_query: Query<&Emplacement>,
+    mut commands: Commands,
+) {
+    let Ok((home_base_transform, home_base_size)) = home_base_query.single() else {
+        return;
+    };
+    let Some(free) = (0..EMPLACEMENTS.len()).find(|index| emplacement_query.iter().all(|taken| taken.0 != *index)) else {
+        commands.log_warning("All turret spots around the base are taken!");
+        return;
+    };
+    let Ok(mut inventory) = inventory_query.single_mut() else {
+        commands.log_error("No inventory found!");
+        return;
+    };
+    if !inventory.take(TURRET_PART_COST) {
+        commands.log_warning(format!("Building a turret needs {} parts!", TURRET_PART_COST));
+        return;
+    }
+
+    let half_size = home_base_size.0 / 2.0 + Vec2::splat(EMPLACEMENT_MARGIN);
+    let position = home_base_transform.translation.xy() + EMPLACEMENTS[free] * half_size;
+    let turret = Turret {
+        range: TURRET_RANGE,
+        damage: Damage::new(TURRET_DAMAGE, DamageType::Kinetic),
+        cooldown: TURRET_COOLDOWN,
+    };
+    let entity = spawn_turret(&mut commands, position, turret, TURRET_HEALTH, "Turret");
+    commands.entity(entity).insert((Emplacement(free), Obstacle));
+    commands.log_info("Turret built");
+}
+
+// Destroyed turrets are removed, which frees their emplacement for a new one. The base's own auto turret goes down with the base
+pub fn turret_health_system(
+    turret_query: Query<(Entity, &Health), (With<Turret>, Without<HomeBase>)>,
+    mut commands: Commands,
+) {
+    for (entity, health) in turret_query.iter() {
+        if health.is_dead() {
+            commands.entity(entity).despawn();
+            commands.log_warning("A turret was destroyed!");
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use bevy::prelude::*;
+use crate::boss::BossPhases;
+use crate::enemies::Boss;
+use crate::Health;
+
+const BAR_WIDTH: f32 = 400.0;
+const BAR_COLOR: Color = Color::srgb(0.8, 0.15, 0.2);
+
+#[derive(Component)]
+pub struct BossBar;
+
+#[derive(Component)]
+pub struct BossBarFill;
+
+#[derive(Component)]
+pub struct BossBarLabel;
+
+pub fn setup_boss_bar(mut commands: Commands) {
+    commands.spawn((
+        BossBar,
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(70.0), // just below the HUD
+            left: Val::Percent(50.0),
+            margin: UiRect::left(Val::Px(-BAR_WIDTH / 2.0)),
+            width: Val::Px(BAR_WIDTH),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            display: Display::None, // shown while there is a boss
+            ..default()
+        },
+        Name::new("Boss Bar"),
+    )).with_children(|parent| {
+        parent.spawn((
+            BossBarLabel,
+            Text::new(""),
+            TextFont {
+                font_size: 16.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+        ));
+        parent.spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Px(14.0),
+                border: UiRect::all(Val::Px(2.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.15, 0.15, 0.15)),
+            BorderColor::all(Color::BLACK),
+        )).with_children(|bar| {
+            bar.spawn((
+                BossBarFill,
+                Node {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
+                BackgroundColor(BAR_COLOR),
+            ));
+        });
+    });
+}
+
+// Follows the boss health and phase, hides the bar once the boss is gone
+pub fn boss_bar_system(
+    boss_query: Query<(Ref<Health>, &Name, Option<Ref<BossPhases>>), With<Boss>>,
+    mut bar_query: Query<&mut Node, (With<BossBar>, Without<BossBarFill>)>,
+    mut fill_query: Query<&mut Node, (With<BossBarFill>, Without<BossBar>)>,
+    mut label_query: Query<&mut Text, With<BossBarLabel>>,
+) {
+    let boss = boss_query.iter().next();
+    for mut node in bar_query.iter_mut() {
+        let display = if boss.is_some() { Display::Flex } else { Display::None };
+        if node.display != display {
+            node.display = display;
+        }
+    }
+
+    let Some((health, name, maybe_phases)) = boss else {
+        return;
+    };
+    let phases_changed = maybe_phases.as_ref().is_some_and(|phases| phases.is_changed());
+    if !health.is_changed() && !phases_changed {
+        return;
+    }
+
+    for mut node in fill_query.iter_mut() {
+        node.width = Val::Percent(100.0 * health.current / health.max.max(1.0));
+    }
+    for mut text in label_query.iter_mut() {
+        text.0 = match maybe_phases.as_ref().and_then(|phases| phases.name().map(|phase| (phases.number(), phase))) {
+            Some((number, phase)) => format!("{} - Phase {}: {}", name, number, phase),
+            None => name.to_string(),
+        };
+    }
+}
@@ -1,5 +1,7 @@
 use bevy::prelude::*;
-use crate::ComponentsInventory;
+use crate::{ArmsKind, ComponentsInventory};
+use crate::audio::{SoundCommands, SoundEffect};
+use crate::message_log::GameLogCommands;
 
 const GREEN_COLOR: Color = Color::srgb(0.0, 1.0, 0.0);
 
@@ -25,6 +27,10 @@ pub struct LeftLegSlot;
 #[derive(Component)]
 pub struct RightLegSlot;
 
+// Button that switches the arms of the next Bob between melee and ranged
+#[derive(Component)]
+pub struct ArmsToggle(pub ArmsKind);
+
 // Component to track if a slot is filled
 #[derive(Component)]
 pub struct SlotFilled(pub bool);
@@ -37,7 +43,7 @@ pub fn on_reset_ui(
         if slot_filled.0 {
             slot_filled.0 = false;
             *bg_color = BackgroundColor(Color::BLACK);
-            println!("Reset slot: {:?}", entity);
+            debug!("Reset slot: {:?}", entity);
         }
     }
 }
@@ -46,7 +52,7 @@ pub fn setup_build_bob_ui(mut commands: Commands) {
     commands.spawn((
         Node {
             width: Val::Px(300.0),
-            height: Val::Px(300.0),
+            height: Val::Px(330.0),
             position_type: PositionType::Absolute,
             right: Val::Px(20.0),
             bottom: Val::Px(20.0),
@@ -189,9 +195,52 @@ pub fn setup_build_bob_ui(mut commands: Commands) {
                 SlotFilled(false),
             ));
         });
+
+        // Arms toggle (bottom)
+        parent.spawn((
+            Button,
+            Node {
+                width: Val::Px(140.0),
+                height: Val::Px(24.0),
+                margin: UiRect::top(Val::Px(8.0)),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                border: UiRect::all(Val::Px(2.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.15, 0.15, 0.15)),
+            BorderColor::all(Color::BLACK),
+            ArmsToggle(ArmsKind::default()),
+        )).with_children(|button| {
+            button.spawn((
+                Text::new(format!("Arms: {}", ArmsKind::default().label())),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+        });
     });
 }
 
+pub fn arms_toggle_system(
+    mut toggle_query: Query<(&Interaction, &mut ArmsToggle, &Children), Changed<Interaction>>,
+    mut text_query: Query<&mut Text>,
+) {
+    for (interaction, mut toggle, children) in toggle_query.iter_mut() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        toggle.0 = toggle.0.next();
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(child) {
+                text.0 = format!("Arms: {}", toggle.0.label());
+            }
+        }
+    }
+}
+
 
 pub fn build_bob_ui_system(
     mut interaction_query: Query<
@@ -199,33 +248,29 @@ pub fn build_bob_ui_system(
         (Changed<Interaction>, Or<(With<HeadSlot>, With<BodySlot>, With<LeftArmSlot>, With<RightArmSlot>, With<LeftLegSlot>, With<RightLegSlot>)>)
     >,
     mut inventory_query: Query<&mut ComponentsInventory>,
+    mut commands: Commands,
 ) {
     for (interaction, mut bg_color, mut slot_filled) in &mut interaction_query {
         if *interaction == Interaction::Pressed {
             if let Ok(mut inventory) = inventory_query.single_mut() {
                 // Check if slot is already filled
                 if slot_filled.0 {
-                    println!("Slot already filled!");
+                    commands.log_info("Slot already filled!");
                     continue;
                 }
 
                 // Check if we have components in inventory
-                if inventory.count > 0 {
+                if inventory.take(1) {
                     // Fill the slot (turn green)
                     *bg_color = BackgroundColor(GREEN_COLOR);
                     slot_filled.0 = true;
-                    
-                    // Decrease inventory (you might want to be more specific about which type)
-                    // For now, just decrease the first available component
-                    if inventory.count > 0 {
-                        inventory.count -= 1;
-                        println!("Used 1 component. Remaining: {}", inventory.count);
-                    }
+                    commands.play_sound(SoundEffect::PlacePart);
+                    debug!("Used 1 component. Remaining: {}", inventory.count());
                 } else {
-                    println!("No components available in inventory!");
+                    commands.log_warning("No components available in inventory!");
                 }
             } else {
-                println!("No inventory found!");
+                commands.log_error("No inventory found!");
             }
         }
     }
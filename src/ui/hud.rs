@@ -0,0 +1,159 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use crate::bob_state::{Attacking, BobState, CurrentBobState, Dead, Idling, Moving, Repairing, Returning, Scouting};
+use crate::loot::LootType;
+use crate::{Bob, ComponentsInventory, Enemy, Health, HomeBase, MatchClock};
+
+const HUD_TEXT_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
+const HUD_LOW_HEALTH_COLOR: Color = Color::srgb(1.0, 0.3, 0.3);
+
+// Which value a HUD text shows
+#[derive(Component)]
+pub enum HudField {
+    Parts,
+    Bobs,
+    BaseHealth,
+    Enemies,
+    MatchTime,
+}
+
+pub fn setup_hud(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            left: Val::Percent(25.0),
+            width: Val::Percent(50.0),
+            flex_direction: FlexDirection::Row,
+            flex_wrap: FlexWrap::Wrap,
+            justify_content: JustifyContent::SpaceEvenly,
+            column_gap: Val::Px(16.0),
+            padding: UiRect::all(Val::Px(6.0)),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.1, 0.1, 0.15, 0.7)),
+        Name::new("HUD"),
+    )).with_children(|parent| {
+        for field in [HudField::Parts, HudField::Bobs, HudField::BaseHealth, HudField::Enemies, HudField::MatchTime] {
+            parent.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(HUD_TEXT_COLOR),
+                field,
+            ));
+        }
+    });
+}
+
+// Every state change adds the new state component
+type StateAdded = Or<(Added<Attacking>, Added<Idling>, Added<Scouting>, Added<Returning>, Added<Repairing>, Added<Moving>, Added<Dead>)>;
+
+// Bobs by state, and whether any Bob changed state, was built or is gone since the last frame
+#[derive(SystemParam)]
+pub struct BobTracker<'w, 's> {
+    bobs: Query<'w, 's, CurrentBobState, With<Bob>>,
+    changed: Query<'w, 's, (), (With<Bob>, StateAdded)>,
+    removed: RemovedComponents<'w, 's, Bob>,
+}
+
+impl BobTracker<'_, '_> {
+    fn changed(&mut self) -> bool {
+        !self.changed.is_empty() || self.removed.read().count() > 0
+    }
+
+    fn count(&self, state: BobState) -> usize {
+        self.bobs.iter().filter(|bob| bob.get() == Some(state)).count()
+    }
+}
+
+// Enemies on the field, and whether any spawned or left since the last frame
+#[derive(SystemParam)]
+pub struct EnemyTracker<'w, 's> {
+    enemies: Query<'w, 's, (), With<Enemy>>,
+    added: Query<'w, 's, (), Added<Enemy>>,
+    removed: RemovedComponents<'w, 's, Enemy>,
+}
+
+impl EnemyTracker<'_, '_> {
+    fn changed(&mut self) -> bool {
+        !self.added.is_empty() || self.removed.read().count() > 0
+    }
+
+    fn count(&self) -> usize {
+        self.enemies.iter().count()
+    }
+}
+
+// Every field is only rewritten when the data behind it changed
+pub fn hud_system(
+    inventory_query: Query<Ref<ComponentsInventory>>,
+    mut bobs: BobTracker,
+    home_base_query: Query<Ref<Health>, With<HomeBase>>,
+    mut enemies: EnemyTracker,
+    match_clock: Res<MatchClock>,
+    mut hud_query: Query<(&HudField, &mut Text, &mut TextColor)>,
+) {
+    let bobs_changed = bobs.changed();
+    let enemies_changed = enemies.changed();
+
+    for (field, mut text, mut color) in hud_query.iter_mut() {
+        // Freshly spawned HUD texts are empty and always need a first value
+        let first_fill = text.0.is_empty();
+        match field {
+            HudField::Parts => {
+                let Ok(inventory) = inventory_query.single() else {
+                    continue;
+                };
+                if inventory.is_changed() || first_fill {
+                    text.0 = LootType::ALL
+                        .iter()
+                        .map(|part| format!("{}: {}", part.label(), inventory.get(*part)))
+                        .collect::<Vec<_>>()
+                        .join("  ");
+                }
+            },
+            HudField::Bobs => {
+                if bobs_changed || first_fill {
+                    text.0 = format!(
+                        "Bobs idle: {}  attacking: {}  scouting: {}",
+                        bobs.count(BobState::Idling),
+                        bobs.count(BobState::Attacking),
+                        bobs.count(BobState::Scouting),
+                    );
+                }
+            },
+            HudField::BaseHealth => {
+                let Some(health) = home_base_query.iter().next() else {
+                    continue;
+                };
+                if health.is_changed() || first_fill {
+                    text.0 = format!("Base: {:.0}/{:.0}", health.current, health.max);
+                    color.0 = health_color(&health);
+                }
+            },
+            HudField::Enemies => {
+                if enemies_changed || first_fill {
+                    text.0 = format!("Enemies: {}", enemies.count());
+                }
+            },
+            HudField::MatchTime => {
+                // The clock ticks every frame, only touch the text when the shown second changes
+                let seconds = match_clock.elapsed as u32;
+                let time = format!("Time {:02}:{:02}", seconds / 60, seconds % 60);
+                if text.0 != time {
+                    text.0 = time;
+                }
+            },
+        }
+    }
+}
+
+fn health_color(health: &Health) -> Color {
+    if health.current < health.max * 0.25 {
+        HUD_LOW_HEALTH_COLOR
+    } else {
+        HUD_TEXT_COLOR
+    }
+}
@@ -0,0 +1,49 @@
+use bevy::prelude::*;
+use crate::formation::Formation;
+
+#[derive(Component)]
+pub struct IdleGridLabel;
+
+pub fn setup_idle_grid_ui(mut commands: Commands) {
+    commands.spawn((
+        IdleGridLabel,
+        Text::new(""),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.9, 0.9, 0.9)),
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(20.0),
+            top: Val::Px(20.0),
+            ..default()
+        },
+    ));
+}
+
+// Shows how many Bobs are idling and which rows of the formation are on screen
+pub fn idle_grid_ui_system(
+    formation: Res<Formation>,
+    mut label_query: Query<&mut Text, With<IdleGridLabel>>,
+) {
+    if !formation.is_changed() {
+        return;
+    }
+
+    for mut text in label_query.iter_mut() {
+        let rows = formation.rows();
+        let visible = formation.visible_row_range();
+        text.0 = if rows > formation.visible_rows {
+            format!(
+                "Idle Bobs: {} (rows {}-{} of {}, scroll to see more)",
+                formation.len(),
+                visible.start + 1,
+                visible.end,
+                rows,
+            )
+        } else {
+            format!("Idle Bobs: {}", formation.len())
+        };
+    }
+}
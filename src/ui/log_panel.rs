@@ -0,0 +1,164 @@
+use bevy::{input::mouse::AccumulatedMouseScroll, prelude::*, ui::RelativeCursorPosition};
+use crate::message_log::{GameLog, MessageLog, Severity};
+
+const TOAST_SECONDS: f32 = 3.0;
+const MAX_TOASTS: usize = 4;
+const SCROLL_LINE_HEIGHT: f32 = 20.0;
+
+#[derive(Component)]
+pub struct MessageLogPanel;
+
+#[derive(Component)]
+pub struct ToastContainer;
+
+#[derive(Component)]
+pub struct Toast(Timer);
+
+pub fn setup_message_log_ui(mut commands: Commands) {
+    // Scrolling list of every message, newest on top
+    commands.spawn((
+        MessageLogPanel,
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(20.0),
+            top: Val::Px(45.0),
+            width: Val::Px(320.0),
+            height: Val::Px(150.0),
+            flex_direction: FlexDirection::Column,
+            overflow: Overflow::scroll_y(),
+            padding: UiRect::all(Val::Px(6.0)),
+            border: UiRect::all(Val::Px(1.0)),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.1, 0.1, 0.15, 0.7)),
+        BorderColor::all(Color::srgb(0.5, 0.5, 0.6)),
+        ScrollPosition::default(),
+        RelativeCursorPosition::default(),
+        Name::new("Message Log"),
+    ));
+
+    // Toasts pop up below the HUD
+    commands.spawn((
+        ToastContainer,
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(60.0),
+            width: Val::Percent(100.0),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            row_gap: Val::Px(4.0),
+            ..default()
+        },
+        Pickable::IGNORE,
+        Name::new("Toasts"),
+    ));
+}
+
+// Rebuilds the panel whenever a message was added
+pub fn message_log_ui_system(
+    message_log: Res<MessageLog>,
+    panel_query: Query<Entity, With<MessageLogPanel>>,
+    mut commands: Commands,
+) {
+    if !message_log.is_changed() {
+        return;
+    }
+
+    for panel in panel_query.iter() {
+        commands.entity(panel).despawn_related::<Children>().with_children(|parent| {
+            for entry in message_log.entries() {
+                let seconds = entry.time as u32;
+                parent.spawn((
+                    Text::new(format!("[{:02}:{:02}] {}", seconds / 60, seconds % 60, entry.text)),
+                    TextFont {
+                        font_size: 14.0,
+                        ..default()
+                    },
+                    TextColor(entry.severity.color()),
+                ));
+            }
+        });
+    }
+}
+
+pub fn message_log_scroll_system(
+    mouse_scroll: Res<AccumulatedMouseScroll>,
+    mut panel_query: Query<(&mut ScrollPosition, &RelativeCursorPosition, &ComputedNode), With<MessageLogPanel>>,
+) {
+    if mouse_scroll.delta.y == 0.0 {
+        return;
+    }
+
+    for (mut scroll_position, cursor, computed_node) in panel_query.iter_mut() {
+        if !cursor.cursor_over() {
+            continue;
+        }
+        let max_scroll = ((computed_node.content_size.y - computed_node.size.y) * computed_node.inverse_scale_factor).max(0.0);
+        scroll_position.y = (scroll_position.y - mouse_scroll.delta.y * SCROLL_LINE_HEIGHT).clamp(0.0, max_scroll);
+    }
+}
+
+// Warnings and errors also pop up as a toast so the player notices them
+pub fn on_game_log_toast(
+    trigger: On<GameLog>,
+    container_query: Query<(Entity, Option<&Children>), With<ToastContainer>>,
+    mut commands: Commands,
+) {
+    if trigger.severity == Severity::Info {
+        return;
+    }
+    let Ok((container, maybe_children)) = container_query.single() else {
+        return;
+    };
+
+    // Drop the oldest toast when too many are on screen
+    if let Some(children) = maybe_children
+        && children.len() >= MAX_TOASTS
+    {
+        commands.entity(children[0]).despawn();
+    }
+
+    commands.entity(container).with_children(|parent| {
+        parent.spawn((
+            Toast(Timer::from_seconds(TOAST_SECONDS, TimerMode::Once)),
+            Node {
+                padding: UiRect::axes(Val::Px(12.0), Val::Px(6.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.75)),
+        )).with_children(|toast| {
+            toast.spawn((
+                Text::new(trigger.text.clone()),
+                TextFont {
+                    font_size: 18.0,
+                    ..default()
+                },
+                TextColor(trigger.severity.color()),
+            ));
+        });
+    });
+}
+
+// Fades toasts out over their lifetime and removes them when done
+pub fn toast_system(
+    mut toast_query: Query<(Entity, &mut Toast, &mut BackgroundColor, &Children)>,
+    mut text_query: Query<&mut TextColor>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (entity, mut toast, mut background, children) in toast_query.iter_mut() {
+        toast.0.tick(time.delta());
+        if toast.0.is_finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let alpha = toast.0.fraction_remaining().min(0.5) * 2.0; // stay solid for the first half
+        background.0.set_alpha(0.75 * alpha);
+        for child in children.iter() {
+            if let Ok(mut text_color) = text_query.get_mut(child) {
+                text_color.0.set_alpha(alpha);
+            }
+        }
+    }
+}
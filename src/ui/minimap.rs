@@ -0,0 +1,241 @@
+use bevy::{prelude::*, ui::RelativeCursorPosition};
+use crate::bob_state::{AttackOrder, BobState, BobStateCommands, CurrentBobState, Dead, MoveOrder};
+use crate::camera::CameraController;
+use crate::enemies::Boss;
+use crate::map::BattleMap;
+use crate::message_log::GameLogCommands;
+use crate::{Attack, Bob, Enemy, Health, HomeBase, Selected, Size};
+
+const MINIMAP_WIDTH: f32 = 240.0; // the height follows the map's aspect ratio
+const ORDER_PICK_RADIUS: f32 = 60.0; // world distance from an enemy edge a right click still counts as clicking it
+const BASE_COLOR: Color = Color::srgb(0.2, 0.9, 0.3);
+const ENEMY_COLOR: Color = Color::srgb(1.0, 0.2, 0.2);
+const SITE_COLOR: Color = Color::srgb(1.0, 0.85, 0.2);
+
+#[derive(Component)]
+pub struct Minimap;
+
+// Outline of what the camera currently shows
+#[derive(Component)]
+pub struct MinimapViewport;
+
+// Dot on the minimap following a world entity
+#[derive(Component)]
+pub struct MinimapMarker {
+    target: Entity,
+    size: f32,
+}
+
+// World entities that already have a dot on the minimap
+#[derive(Component)]
+pub struct OnMinimap;
+
+fn bob_color(state: BobState) -> Color {
+    match state {
+        BobState::Idling => Color::srgb(0.8, 0.8, 0.8),
+        BobState::Attacking => Color::srgb(1.0, 0.5, 0.0),
+        BobState::Scouting => Color::srgb(0.3, 0.5, 1.0),
+        BobState::Returning => Color::srgb(0.5, 0.8, 1.0),
+        BobState::Repairing => Color::srgb(0.1, 0.6, 0.2),
+        BobState::Moving => Color::srgb(0.9, 0.3, 0.9),
+        BobState::Dead => Color::srgb(0.3, 0.3, 0.3),
+    }
+}
+
+// Where a world position ends up on the minimap, in percent from the top left corner
+fn to_minimap(bounds: Rect, position: Vec2) -> Vec2 {
+    let relative = (position - bounds.min) / bounds.size();
+    Vec2::new(relative.x, 1.0 - relative.y) * 100.0
+}
+
+fn marker_node(bounds: Rect, position: Vec2, size: f32) -> Node {
+    let percent = to_minimap(bounds, position);
+    Node {
+        position_type: PositionType::Absolute,
+        left: Val::Percent(percent.x),
+        top: Val::Percent(percent.y),
+        width: Val::Px(size),
+        height: Val::Px(size),
+        margin: UiRect::new(Val::Px(-size / 2.0), Val::ZERO, Val::Px(-size / 2.0), Val::ZERO), // centred on the position
+        border: UiRect::all(Val::Px(1.0)),
+        ..default()
+    }
+}
+
+pub fn setup_minimap(mut commands: Commands, map: Res<BattleMap>) {
+    commands.spawn((
+        Minimap,
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(20.0),
+            bottom: Val::Px(20.0),
+            width: Val::Px(MINIMAP_WIDTH),
+            height: Val::Px(MINIMAP_WIDTH * map.bounds.height() / map.bounds.width()),
+            border: UiRect::all(Val::Px(2.0)),
+            overflow: Overflow::clip(),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.1, 0.1, 0.15, 0.85)),
+        BorderColor::all(Color::srgb(0.5, 0.5, 0.6)),
+        RelativeCursorPosition::default(),
+        GlobalZIndex(5),
+        Name::new("Minimap"),
+    )).with_children(|parent| {
+        // Scouting sites are hollow squares
+        for site in map.scout_sites.iter() {
+            parent.spawn((
+                marker_node(map.bounds, *site, 8.0),
+                BorderColor::all(SITE_COLOR),
+                Pickable::IGNORE,
+            ));
+        }
+        parent.spawn((
+            MinimapViewport,
+            Node {
+                position_type: PositionType::Absolute,
+                border: UiRect::all(Val::Px(1.0)),
+                ..default()
+            },
+            BorderColor::all(Color::WHITE),
+            Pickable::IGNORE,
+        ));
+    }).observe(on_minimap_clicked);
+}
+
+// Gives the base, enemies and Bobs a dot and keeps the dots on their entity, coloured by what it is doing
+pub fn minimap_marker_system(
+    new_query: Query<(Entity, Has<HomeBase>, Has<Boss>), (Or<(With<Bob>, With<Enemy>, With<HomeBase>)>, Without<OnMinimap>)>,
+    tracked_query: Query<(&Transform, CurrentBobState, Has<HomeBase>, Has<Selected>), With<OnMinimap>>,
+    mut marker_query: Query<(Entity, &MinimapMarker, &mut Node, &mut BackgroundColor, &mut BorderColor)>,
+    minimap_query: Query<Entity, With<Minimap>>,
+    map: Res<BattleMap>,
+    mut commands: Commands,
+) {
+    let Ok(minimap) = minimap_query.single() else {
+        return;
+    };
+
+    for (entity, is_base, is_boss) in new_query.iter() {
+        let size = if is_base || is_boss { 12.0 } else { 6.0 };
+        commands.entity(entity).insert(OnMinimap);
+        commands.spawn((
+            MinimapMarker { target: entity, size },
+            marker_node(map.bounds, Vec2::ZERO, size),
+            BackgroundColor(Color::NONE),
+            BorderColor::all(Color::NONE),
+            Pickable::IGNORE,
+            ChildOf(minimap),
+        ));
+    }
+
+    for (marker, dot, mut node, mut background, mut border) in marker_query.iter_mut() {
+        let Ok((transform, state, is_base, is_selected)) = tracked_query.get(dot.target) else {
+            // The entity is gone
+            commands.entity(marker).despawn();
+            continue;
+        };
+
+        let percent = to_minimap(map.bounds, transform.translation.xy());
+        node.left = Val::Percent(percent.x);
+        node.top = Val::Percent(percent.y);
+        node.width = Val::Px(dot.size);
+        node.height = Val::Px(dot.size);
+
+        let color = match state.get() {
+            Some(state) => bob_color(state),
+            None if is_base => BASE_COLOR,
+            None => ENEMY_COLOR,
+        };
+        if background.0 != color {
+            background.0 = color;
+        }
+        // Selected Bobs get a white outline
+        let outline = if is_selected { Color::WHITE } else { Color::NONE };
+        if border.top != outline {
+            *border = BorderColor::all(outline);
+        }
+    }
+}
+
+pub fn minimap_viewport_system(
+    camera_query: Query<(&Transform, &Projection), With<CameraController>>,
+    mut viewport_query: Query<&mut Node, With<MinimapViewport>>,
+    map: Res<BattleMap>,
+) {
+    let Ok((transform, Projection::Orthographic(ortho))) = camera_query.single() else {
+        return;
+    };
+    let center = transform.translation.xy();
+    let top_left = to_minimap(map.bounds, center + Vec2::new(ortho.area.min.x, ortho.area.max.y));
+    let size = ortho.area.size() / map.bounds.size() * 100.0;
+
+    for mut node in viewport_query.iter_mut() {
+        node.left = Val::Percent(top_left.x);
+        node.top = Val::Percent(top_left.y);
+        node.width = Val::Percent(size.x);
+        node.height = Val::Percent(size.y);
+    }
+}
+
+// Left click moves the camera there, right click orders the selected Bobs to attack the enemy there or move to the spot
+fn on_minimap_clicked(
+    trigger: On<Pointer<Click>>,
+    minimap_query: Query<&RelativeCursorPosition, With<Minimap>>,
+    mut camera_query: Query<(&mut Transform, &mut CameraController)>,
+    selected_query: Query<(Entity, Has<Dead>), (With<Bob>, With<Selected>)>,
+    enemy_query: Query<(Entity, &Transform, &Size, &Health, &Name), (With<Enemy>, Without<CameraController>)>,
+    map: Res<BattleMap>,
+    mut commands: Commands,
+) {
+    let Some(normalized) = minimap_query.single().ok().and_then(|cursor| cursor.normalized) else {
+        return;
+    };
+    // Normalized runs from -0.5 at the top left to 0.5 at the bottom right
+    let point = map.bounds.center() + Vec2::new(normalized.x, -normalized.y) * map.bounds.size();
+
+    match trigger.event.button {
+        PointerButton::Primary => {
+            if let Ok((mut transform, mut controller)) = camera_query.single_mut() {
+                controller.follow = None;
+                transform.translation = point.extend(transform.translation.z);
+            }
+        },
+        PointerButton::Secondary => {
+            let bobs: Vec<Entity> = selected_query
+                .iter()
+                .filter(|(_, is_dead)| !is_dead)
+                .map(|(entity, _)| entity)
+                .collect();
+            if bobs.is_empty() {
+                commands.log_warning("Select a Bob first to give it orders");
+                return;
+            }
+
+            let target = enemy_query
+                .iter()
+                .filter(|(_, _, _, health, _)| !health.is_dead())
+                .map(|(entity, transform, size, _, name)| {
+                    let gap = ((point - transform.translation.xy()).abs() - size.0 / 2.0).max(Vec2::ZERO).length();
+                    (entity, name, gap)
+                })
+                .filter(|(_, _, gap)| *gap <= ORDER_PICK_RADIUS)
+                .min_by(|a, b| a.2.total_cmp(&b.2));
+
+            for bob in bobs {
+                match target {
+                    Some((enemy, _, _)) => {
+                        commands.entity(bob).remove::<Attack>().insert(AttackOrder(enemy)).set_bob_state(BobState::Attacking);
+                    },
+                    None => {
+                        commands.entity(bob).insert(MoveOrder(point)).set_bob_state(BobState::Moving);
+                    },
+                }
+            }
+            match target {
+                Some((_, name, _)) => commands.log_info(format!("Ordered the selected Bob to attack the {}", name)),
+                None => commands.log_info("Ordered the selected Bob to move"),
+            }
+        },
+        PointerButton::Middle => {},
+    }
+}
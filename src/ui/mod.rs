@@ -1,5 +1,21 @@
+pub mod boss_bar;
 pub mod build_bob;
+pub mod hud;
+pub mod idle_grid;
+pub mod log_panel;
+pub mod minimap;
+pub mod settings;
 pub mod state_screens;
+pub mod tech_tree;
+pub mod ui_scale;
 
+pub use boss_bar::*;
 pub use build_bob::*;
-pub use state_screens::*;
\ No newline at end of file
+pub use hud::*;
+pub use idle_grid::*;
+pub use log_panel::*;
+pub use minimap::*;
+pub use settings::*;
+pub use state_screens::*;
+pub use tech_tree::*;
+pub use ui_scale::*;
\ No newline at end of file
@@ -0,0 +1,157 @@
+use bevy::{prelude::*, ui::RelativeCursorPosition};
+use crate::audio::{VolumeChannel, VolumeSettings};
+
+const TRACK_COLOR: Color = Color::srgb(0.25, 0.25, 0.28);
+const FILL_COLOR: Color = Color::srgb(0.3, 0.55, 0.8);
+
+#[derive(Event)]
+pub struct ToggleSettingsEvent;
+
+#[derive(Component)]
+pub struct SettingsPanel;
+
+// Clicking or dragging along the track sets the channel's volume
+#[derive(Component)]
+pub struct VolumeSlider(pub VolumeChannel);
+
+#[derive(Component)]
+pub struct VolumeSliderFill(pub VolumeChannel);
+
+#[derive(Component)]
+pub struct VolumeSliderText(pub VolumeChannel);
+
+pub fn setup_settings_ui(mut commands: Commands) {
+    commands.spawn((
+        SettingsPanel,
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Percent(35.0),
+            top: Val::Percent(20.0),
+            width: Val::Percent(30.0),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(10.0),
+            padding: UiRect::all(Val::Px(12.0)),
+            border: UiRect::all(Val::Px(2.0)),
+            display: Display::None, // opened with the Settings button
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.1, 0.1, 0.15, 0.95)),
+        BorderColor::all(Color::srgb(0.5, 0.5, 0.6)),
+        GlobalZIndex(10),
+        Name::new("Settings"),
+    )).with_children(|parent| {
+        parent.spawn((
+            Text::new("Settings"),
+            TextFont {
+                font_size: 22.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+        ));
+        for channel in VolumeChannel::ALL {
+            spawn_volume_slider(parent, channel);
+        }
+    });
+}
+
+fn spawn_volume_slider(parent: &mut ChildSpawnerCommands, channel: VolumeChannel) {
+    parent.spawn((
+        VolumeSliderText(channel),
+        Text::new(channel.label()),
+        TextFont {
+            font_size: 14.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.9, 0.9, 0.9)),
+    ));
+    parent.spawn((
+        VolumeSlider(channel),
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Px(16.0),
+            border: UiRect::all(Val::Px(1.0)),
+            ..default()
+        },
+        BackgroundColor(TRACK_COLOR),
+        BorderColor::all(Color::BLACK),
+        RelativeCursorPosition::default(),
+    )).with_children(|track| {
+        track.spawn((
+            VolumeSliderFill(channel),
+            Node {
+                width: Val::Percent(0.0), // set by volume_slider_ui_system
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            BackgroundColor(FILL_COLOR),
+            Pickable::IGNORE,
+        ));
+    })
+    .observe(on_slider_click)
+    .observe(on_slider_drag)
+    .observe(on_slider_drag_end);
+}
+
+pub fn on_toggle_settings(
+    _trigger: On<ToggleSettingsEvent>,
+    mut panel_query: Query<&mut Node, With<SettingsPanel>>,
+) {
+    for mut node in panel_query.iter_mut() {
+        node.display = match node.display {
+            Display::None => Display::Flex,
+            _ => Display::None,
+        };
+    }
+}
+
+// Moves the slider to the cursor, false if the cursor is not over the window
+fn set_from_cursor(slider: Entity, slider_query: &Query<(&VolumeSlider, &RelativeCursorPosition)>, settings: &mut VolumeSettings) -> bool {
+    let Ok((slider, cursor)) = slider_query.get(slider) else {
+        return false;
+    };
+    // Normalized runs from -0.5 at the left edge to 0.5 at the right edge
+    let Some(normalized) = cursor.normalized else {
+        return false;
+    };
+    settings.set(slider.0, normalized.x + 0.5);
+    true
+}
+
+fn on_slider_click(
+    trigger: On<Pointer<Click>>,
+    slider_query: Query<(&VolumeSlider, &RelativeCursorPosition)>,
+    mut settings: ResMut<VolumeSettings>,
+) {
+    if set_from_cursor(trigger.entity, &slider_query, &mut settings) {
+        settings.save();
+    }
+}
+
+fn on_slider_drag(
+    trigger: On<Pointer<Drag>>,
+    slider_query: Query<(&VolumeSlider, &RelativeCursorPosition)>,
+    mut settings: ResMut<VolumeSettings>,
+) {
+    set_from_cursor(trigger.entity, &slider_query, &mut settings);
+}
+
+// Saved once the drag is over instead of on every step of it
+fn on_slider_drag_end(_trigger: On<Pointer<DragEnd>>, settings: Res<VolumeSettings>) {
+    settings.save();
+}
+
+pub fn volume_slider_ui_system(
+    settings: Res<VolumeSettings>,
+    mut fill_query: Query<(&VolumeSliderFill, &mut Node)>,
+    mut text_query: Query<(&VolumeSliderText, &mut Text)>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    for (fill, mut node) in fill_query.iter_mut() {
+        node.width = Val::Percent(settings.get(fill.0) * 100.0);
+    }
+    for (label, mut text) in text_query.iter_mut() {
+        text.0 = format!("{} {:.0}%", label.0.label(), settings.get(label.0) * 100.0);
+    }
+}
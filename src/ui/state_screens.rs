@@ -1,5 +1,6 @@
 use bevy::prelude::*;
 use crate::GameStates;
+use crate::difficulty::{Difficulty, DifficultyPreset};
 
 #[derive(Component)]
 pub struct PlayAgainButton;
@@ -7,10 +8,118 @@ pub struct PlayAgainButton;
 #[derive(Component)]
 pub struct WinScreen;
 
+#[derive(Component)]
+pub struct DifficultyScreen;
+
+// Starts the run on this preset
+#[derive(Component)]
+pub struct DifficultyButton(pub DifficultyPreset);
+
 #[derive(Component)]
 pub struct LossScreen;
 
-pub fn win_screen(mut commands: Commands) {
+// Shown before the run starts unless the difficulty came from the command line
+pub fn difficulty_screen(mut commands: Commands) {
+    commands.spawn((
+        DifficultyScreen,
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(12.0),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.9)), // covers the battlefield until a preset is picked
+        GlobalZIndex(20),
+    )).with_children(|parent| {
+        parent.spawn((
+            Text::new("Choose a difficulty"),
+            TextFont {
+                font_size: 40.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+        ));
+        for preset in DifficultyPreset::PICKABLE {
+            parent.spawn((
+                Button,
+                DifficultyButton(preset),
+                Node {
+                    width: Val::Px(360.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    padding: UiRect::all(Val::Px(10.0)),
+                    border: UiRect::all(Val::Px(2.0)),
+                    ..default()
+                },
+                BackgroundColor(Color::srgb(0.2, 0.4, 0.6)),
+                BorderColor::all(Color::BLACK),
+            )).with_children(|button_parent| {
+                button_parent.spawn((
+                    Text::new(preset.label()),
+                    TextFont {
+                        font_size: 24.0,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                ));
+                button_parent.spawn((
+                    Text::new(preset.description()),
+                    TextFont {
+                        font_size: 14.0,
+                        ..default()
+                    },
+                    TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                ));
+            });
+        }
+        parent.spawn((
+            Text::new("Custom runs are set up with command line flags, e.g. --difficulty=custom --enemy-health=1.2"),
+            TextFont {
+                font_size: 14.0,
+                ..default()
+            },
+            TextColor(Color::srgb(0.6, 0.6, 0.6)),
+        ));
+    });
+}
+
+pub fn difficulty_button_system(
+    mut interaction_query: Query<
+        (&Interaction, &DifficultyButton, &mut BackgroundColor),
+        (Changed<Interaction>, With<Button>)
+    >,
+    mut difficulty: ResMut<Difficulty>,
+    mut next_state: ResMut<NextState<GameStates>>,
+) {
+    for (interaction, button, mut color) in interaction_query.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                *difficulty = Difficulty::from_preset(button.0);
+                next_state.set(GameStates::Playing);
+            }
+            Interaction::Hovered => {
+                *color = BackgroundColor(Color::srgb(0.3, 0.55, 0.8));
+            }
+            Interaction::None => {
+                *color = BackgroundColor(Color::srgb(0.2, 0.4, 0.6));
+            }
+        }
+    }
+}
+
+pub fn cleanup_difficulty_screen(
+    mut commands: Commands,
+    query: Query<Entity, With<DifficultyScreen>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+pub fn win_screen(mut commands: Commands, difficulty: Res<Difficulty>) {
     commands.spawn((
         WinScreen,
         Node {
@@ -32,6 +141,15 @@ pub fn win_screen(mut commands: Commands) {
             },
             TextColor(Color::srgb(0.0, 1.0, 0.0)),
         ));
+        // Run summary
+        parent.spawn((
+            Text::new(format!("Difficulty: {}", difficulty.preset.label())),
+            TextFont {
+                font_size: 22.0,
+                ..default()
+            },
+            TextColor(Color::srgb(0.8, 0.8, 0.8)),
+        ));
         // Play Again button
         parent.spawn((
             Button,
@@ -60,7 +178,7 @@ pub fn win_screen(mut commands: Commands) {
     });
 }
 
-pub fn loss_screen(mut commands: Commands) {
+pub fn loss_screen(mut commands: Commands, difficulty: Res<Difficulty>) {
     commands.spawn((
         LossScreen,
         Node {
@@ -82,6 +200,15 @@ pub fn loss_screen(mut commands: Commands) {
             },
             TextColor(Color::srgb(1.0, 0.0, 0.0)),
         ));
+        // Run summary
+        parent.spawn((
+            Text::new(format!("Difficulty: {}", difficulty.preset.label())),
+            TextFont {
+                font_size: 22.0,
+                ..default()
+            },
+            TextColor(Color::srgb(0.8, 0.8, 0.8)),
+        ));
         // Play Again button
         parent.spawn((
             Button,
@@ -123,7 +250,7 @@ pub fn play_again_button_system(
             Interaction::Pressed => {
                 // Set the next state to Playing when button is clicked
                 crate::restart_game();
-                println!("Play Again button pressed - restarting...");
+                info!("Play Again button pressed - restarting...");
             }
             Interaction::Hovered => {
                 *color = BackgroundColor(Color::srgb(0.2, 0.7, 0.2));
@@ -0,0 +1,208 @@
+use bevy::prelude::*;
+use crate::upgrades::{PurchaseUpgradeEvent, TechTree, UpgradeStatus};
+use crate::ComponentsInventory;
+
+const LOCKED_COLOR: Color = Color::srgb(0.25, 0.25, 0.28);
+const AVAILABLE_COLOR: Color = Color::srgb(0.2, 0.4, 0.6);
+const AFFORDABLE_HOVER_COLOR: Color = Color::srgb(0.3, 0.55, 0.8);
+const PURCHASED_COLOR: Color = Color::srgb(0.15, 0.5, 0.2);
+
+#[derive(Event)]
+pub struct ToggleTechTreeEvent;
+
+#[derive(Component)]
+pub struct TechTreePanel;
+
+// One button per upgrade, holding the upgrade id
+#[derive(Component)]
+pub struct UpgradeButton(pub String);
+
+// The cost/status line inside an upgrade button
+#[derive(Component)]
+pub struct UpgradeStatusText(pub String);
+
+pub fn setup_tech_tree_ui(mut commands: Commands, tech_tree: Res<TechTree>) {
+    let tier_count = tech_tree
+        .upgrades()
+        .iter()
+        .map(|upgrade| tech_tree.tier(&upgrade.id) + 1)
+        .max()
+        .unwrap_or(0);
+
+    commands.spawn((
+        TechTreePanel,
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Percent(15.0),
+            top: Val::Percent(12.0),
+            width: Val::Percent(70.0),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            padding: UiRect::all(Val::Px(12.0)),
+            border: UiRect::all(Val::Px(2.0)),
+            display: Display::None, // opened with the Upgrades button
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.1, 0.1, 0.15, 0.95)),
+        BorderColor::all(Color::srgb(0.5, 0.5, 0.6)),
+        GlobalZIndex(10),
+        Name::new("Tech Tree"),
+    )).with_children(|parent| {
+        parent.spawn((
+            Text::new("Home Base Upgrades"),
+            TextFont {
+                font_size: 22.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+            Node {
+                margin: UiRect::bottom(Val::Px(10.0)),
+                ..default()
+            },
+        ));
+
+        // One column per tier, prerequisites are always to the left
+        parent.spawn(Node {
+            width: Val::Percent(100.0),
+            flex_direction: FlexDirection::Row,
+            justify_content: JustifyContent::SpaceEvenly,
+            align_items: AlignItems::FlexStart,
+            ..default()
+        }).with_children(|columns| {
+            for tier in 0..tier_count {
+                columns.spawn(Node {
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(8.0),
+                    ..default()
+                }).with_children(|column| {
+                    for upgrade in tech_tree.upgrades().iter().filter(|upgrade| tech_tree.tier(&upgrade.id) == tier) {
+                        spawn_upgrade_button(column, &tech_tree, &upgrade.id);
+                    }
+                });
+            }
+        });
+    });
+}
+
+fn spawn_upgrade_button(parent: &mut ChildSpawnerCommands, tech_tree: &TechTree, id: &str) {
+    let Some(upgrade) = tech_tree.get(id) else {
+        return;
+    };
+    let requires = upgrade.requires
+        .iter()
+        .filter_map(|required| tech_tree.get(required).map(|def| def.name.clone()))
+        .collect::<Vec<_>>();
+
+    parent.spawn((
+        Button,
+        UpgradeButton(upgrade.id.clone()),
+        Node {
+            width: Val::Px(180.0),
+            flex_direction: FlexDirection::Column,
+            padding: UiRect::all(Val::Px(6.0)),
+            border: UiRect::all(Val::Px(2.0)),
+            ..default()
+        },
+        BackgroundColor(LOCKED_COLOR),
+        BorderColor::all(Color::BLACK),
+    )).with_children(|button| {
+        button.spawn((
+            Text::new(upgrade.name.clone()),
+            TextFont {
+                font_size: 16.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+        ));
+        button.spawn((
+            Text::new(upgrade.description.clone()),
+            TextFont {
+                font_size: 12.0,
+                ..default()
+            },
+            TextColor(Color::srgb(0.8, 0.8, 0.8)),
+        ));
+        if !requires.is_empty() {
+            button.spawn((
+                Text::new(format!("Needs: {}", requires.join(", "))),
+                TextFont {
+                    font_size: 12.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.7, 0.7, 0.7)),
+            ));
+        }
+        button.spawn((
+            Text::new(""),
+            TextFont {
+                font_size: 13.0,
+                ..default()
+            },
+            TextColor(Color::srgb(1.0, 0.85, 0.3)),
+            UpgradeStatusText(upgrade.id.clone()),
+        ));
+    });
+}
+
+pub fn on_toggle_tech_tree(
+    _trigger: On<ToggleTechTreeEvent>,
+    mut panel_query: Query<&mut Node, With<TechTreePanel>>,
+) {
+    for mut node in panel_query.iter_mut() {
+        node.display = match node.display {
+            Display::None => Display::Flex,
+            _ => Display::None,
+        };
+    }
+}
+
+pub fn tech_tree_button_system(
+    interaction_query: Query<(&Interaction, &UpgradeButton), (Changed<Interaction>, With<Button>)>,
+    mut commands: Commands,
+) {
+    for (interaction, button) in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            commands.trigger(PurchaseUpgradeEvent { id: button.0.clone() });
+        }
+    }
+}
+
+// Recolours the upgrades whenever something was bought, the inventory changed or the mouse moved over them
+pub fn tech_tree_ui_system(
+    tech_tree: Res<TechTree>,
+    inventory_query: Query<Ref<ComponentsInventory>>,
+    mut button_query: Query<(Ref<Interaction>, &UpgradeButton, &mut BackgroundColor)>,
+    mut status_query: Query<(&UpgradeStatusText, &mut Text)>,
+) {
+    let Ok(inventory) = inventory_query.single() else {
+        return;
+    };
+    let data_changed = tech_tree.is_changed() || inventory.is_changed();
+
+    for (interaction, button, mut background) in button_query.iter_mut() {
+        if !data_changed && !interaction.is_changed() {
+            continue;
+        }
+        let affordable = tech_tree.get(&button.0).is_some_and(|upgrade| inventory.count() >= upgrade.cost);
+        background.0 = match (tech_tree.status(&button.0), *interaction) {
+            (UpgradeStatus::Purchased, _) => PURCHASED_COLOR,
+            (UpgradeStatus::Locked, _) => LOCKED_COLOR,
+            (UpgradeStatus::Available, Interaction::Hovered | Interaction::Pressed) if affordable => AFFORDABLE_HOVER_COLOR,
+            (UpgradeStatus::Available, _) => AVAILABLE_COLOR,
+        };
+    }
+
+    if !data_changed {
+        return;
+    }
+    for (status_text, mut text) in status_query.iter_mut() {
+        let Some(upgrade) = tech_tree.get(&status_text.0) else {
+            continue;
+        };
+        text.0 = match tech_tree.status(&upgrade.id) {
+            UpgradeStatus::Purchased => "Built".to_string(),
+            UpgradeStatus::Locked => format!("Locked - {} parts", upgrade.cost),
+            UpgradeStatus::Available => format!("{} parts", upgrade.cost),
+        };
+    }
+}
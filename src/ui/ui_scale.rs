@@ -0,0 +1,80 @@
+use bevy::prelude::*;
+use crate::camera::LOGICAL_RESOLUTION;
+use crate::message_log::GameLogCommands;
+
+const MIN_UI_SCALE: f32 = 0.5;
+const MAX_UI_SCALE: f32 = 2.0;
+const UI_SCALE_STEP: f32 = 0.1;
+
+/// Player UI scale on top of the automatic scaling with the window size.
+/// Set with `--ui-scale=1.25` (and `--fixed-ui` to turn the automatic part off) or Ctrl +, Ctrl - and Ctrl 0 in game.
+#[derive(Resource, Debug, Clone)]
+pub struct UiSettings {
+    pub scale: f32,
+    pub follow_window: bool, // grow and shrink with the window, the logical resolution being 1.0
+}
+
+impl Default for UiSettings {
+    fn default() -> Self {
+        Self { scale: 1.0, follow_window: true }
+    }
+}
+
+impl UiSettings {
+    pub fn from_args(args: impl Iterator<Item = String>, warnings: &mut Vec<String>) -> Self {
+        let mut settings = Self::default();
+        for arg in args {
+            if arg == "--fixed-ui" {
+                settings.follow_window = false;
+            } else if let Some(value) = arg.strip_prefix("--ui-scale=") {
+                match value.parse::<f32>() {
+                    Ok(scale) => settings.scale = scale.clamp(MIN_UI_SCALE, MAX_UI_SCALE),
+                    Err(_) => warnings.push(format!("Could not parse '{}', keeping the default UI scale", arg)),
+                }
+            }
+        }
+        settings
+    }
+}
+
+pub fn ui_scale_settings_system(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<UiSettings>,
+    mut commands: Commands,
+) {
+    if !keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return;
+    }
+    let scale = if keys.any_just_pressed([KeyCode::Equal, KeyCode::NumpadAdd]) {
+        settings.scale + UI_SCALE_STEP
+    } else if keys.any_just_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) {
+        settings.scale - UI_SCALE_STEP
+    } else if keys.any_just_pressed([KeyCode::Digit0, KeyCode::Numpad0]) {
+        1.0
+    } else {
+        return;
+    };
+
+    settings.scale = scale.clamp(MIN_UI_SCALE, MAX_UI_SCALE);
+    commands.log_info(format!("UI scale {:.0}%", settings.scale * 100.0));
+}
+
+// Panels are laid out in pixels for the logical resolution, the UI scale keeps them in proportion to the window
+pub fn ui_scale_system(
+    settings: Res<UiSettings>,
+    q_window: Query<&Window>,
+    mut ui_scale: ResMut<UiScale>,
+) {
+    let Ok(window) = q_window.single() else {
+        return;
+    };
+    let window_factor = if settings.follow_window {
+        (window.width() / LOGICAL_RESOLUTION.x).min(window.height() / LOGICAL_RESOLUTION.y)
+    } else {
+        1.0
+    };
+    let scale = (settings.scale * window_factor).clamp(MIN_UI_SCALE, MAX_UI_SCALE);
+    if ui_scale.0 != scale {
+        ui_scale.0 = scale;
+    }
+}
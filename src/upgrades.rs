@@ -0,0 +1,170 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::collections::HashSet;
+use crate::formation::Formation;
+use crate::message_log::GameLogCommands;
+use crate::damage::{Damage, DamageType, MAX_ARMOUR};
+use crate::turret::Turret;
+use crate::{Armour, ComponentsInventory, HomeBase};
+
+const UPGRADES_RON: &str = include_str!("../assets/data/upgrades.ron");
+
+#[derive(Deserialize, Debug, Clone)]
+pub enum UpgradeEffect {
+    FormationRows(usize), // the formation has no cap, this is how many of its rows stay on the field
+    Armour(f32),
+    ScoutingYield(u32),
+    AutoTurret {
+        damage: f32,
+        cooldown: f32,
+        range: f32,
+        #[serde(default)]
+        damage_type: DamageType,
+    },
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct UpgradeDef {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub cost: u32,
+    #[serde(default)]
+    pub requires: Vec<String>,
+    pub effect: UpgradeEffect,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpgradeStatus {
+    Locked,    // prerequisites missing
+    Available,
+    Purchased,
+}
+
+/// Every home base upgrade from `assets/data/upgrades.ron` and which of them were bought this run
+#[derive(Resource)]
+pub struct TechTree {
+    upgrades: Vec<UpgradeDef>,
+    purchased: HashSet<String>,
+    pub scouting_bonus: u32, // extra parts per scouting trip
+}
+
+impl Default for TechTree {
+    fn default() -> Self {
+        let upgrades = ron::from_str(UPGRADES_RON).unwrap_or_else(|error| {
+            error!("Could not read upgrades.ron: {}", error);
+            Vec::new()
+        });
+        Self {
+            upgrades,
+            purchased: HashSet::new(),
+            scouting_bonus: 0,
+        }
+    }
+}
+
+impl TechTree {
+    pub fn upgrades(&self) -> &[UpgradeDef] {
+        &self.upgrades
+    }
+
+    pub fn get(&self, id: &str) -> Option<&UpgradeDef> {
+        self.upgrades.iter().find(|upgrade| upgrade.id == id)
+    }
+
+    pub fn status(&self, id: &str) -> UpgradeStatus {
+        if self.purchased.contains(id) {
+            return UpgradeStatus::Purchased;
+        }
+        match self.get(id) {
+            Some(upgrade) if upgrade.requires.iter().all(|required| self.purchased.contains(required)) => UpgradeStatus::Available,
+            _ => UpgradeStatus::Locked,
+        }
+    }
+
+    // Column in the tech tree UI: upgrades without prerequisites come first
+    pub fn tier(&self, id: &str) -> usize {
+        self.tier_with_depth(id, 0)
+    }
+
+    fn tier_with_depth(&self, id: &str, depth: usize) -> usize {
+        // Guard against prerequisite cycles in the data file
+        if depth > self.upgrades.len() {
+            return depth;
+        }
+        self.get(id)
+            .map(|upgrade| {
+                upgrade.requires
+                    .iter()
+                    .map(|required| self.tier_with_depth(required, depth + 1) + 1)
+                    .max()
+                    .unwrap_or(0)
+            })
+            .unwrap_or(0)
+    }
+}
+
+#[derive(Event)]
+pub struct PurchaseUpgradeEvent {
+    pub id: String,
+}
+
+pub fn on_purchase_upgrade(
+    trigger: On<PurchaseUpgradeEvent>,
+    mut tech_tree: ResMut<TechTree>,
+    mut inventory_query: Query<&mut ComponentsInventory>,
+    mut formation: ResMut<Formation>,
+    mut home_base_query: Query<(Entity, Option<&mut Armour>), With<HomeBase>>,
+    mut commands: Commands,
+) {
+    let Some(upgrade) = tech_tree.get(&trigger.id).cloned() else {
+        commands.log_error(format!("Unknown upgrade '{}'", trigger.id));
+        return;
+    };
+    match tech_tree.status(&upgrade.id) {
+        UpgradeStatus::Purchased => {
+            commands.log_info(format!("{} is already built", upgrade.name));
+            return;
+        },
+        UpgradeStatus::Locked => {
+            commands.log_warning(format!("{} needs other upgrades first", upgrade.name));
+            return;
+        },
+        UpgradeStatus::Available => {},
+    }
+
+    let Ok(mut inventory) = inventory_query.single_mut() else {
+        commands.log_error("No inventory found!");
+        return;
+    };
+    if !inventory.take(upgrade.cost) {
+        commands.log_warning(format!("{} needs {} parts!", upgrade.name, upgrade.cost));
+        return;
+    }
+
+    match upgrade.effect {
+        UpgradeEffect::FormationRows(rows) => {
+            formation.visible_rows += rows;
+        },
+        UpgradeEffect::Armour(amount) => {
+            if let Ok((home_base, maybe_armour)) = home_base_query.single_mut() {
+                match maybe_armour {
+                    Some(mut armour) => armour.0 = (armour.0 + amount).min(MAX_ARMOUR),
+                    None => { commands.entity(home_base).insert(Armour(amount.min(MAX_ARMOUR))); },
+                }
+            }
+        },
+        UpgradeEffect::ScoutingYield(bonus) => {
+            tech_tree.scouting_bonus += bonus;
+        },
+        UpgradeEffect::AutoTurret { damage, cooldown, range, damage_type } => {
+            // Mounted on the base itself, the turrets built around it are a separate thing
+            if let Ok((home_base, _)) = home_base_query.single() {
+                commands.entity(home_base).insert(Turret { range, damage: Damage::new(damage, damage_type), cooldown });
+            }
+        },
+    }
+
+    tech_tree.purchased.insert(upgrade.id.clone());
+    commands.log_info(format!("Upgrade built: {}", upgrade.name));
+}